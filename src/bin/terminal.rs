@@ -0,0 +1,39 @@
+use chess_engine::board::fen::INITIAL_FEN;
+use chess_engine::board::position::Position;
+use chess_engine::terminal::{Glyphs, TerminalGame};
+use std::io;
+
+const USAGE: &str = "usage: terminal [--ascii] [fen]";
+
+/// Plays a game in the terminal, moves are read from stdin so games can be scripted as well
+fn main() {
+    let mut glyphs = Glyphs::Unicode;
+    let mut fen_args = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--ascii" => glyphs = Glyphs::Ascii,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => fen_args.push(arg),
+        }
+    }
+    let fen = match fen_args.is_empty() {
+        true => INITIAL_FEN.to_string(),
+        false => fen_args.join(" "),
+    };
+    let position = match Position::from_fen(&fen) {
+        Ok(position) => position,
+        Err(err) => {
+            eprintln!("ERROR: invalid FEN: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut game = TerminalGame::new(position, glyphs);
+    if let Err(err) = game.run(io::stdin().lock(), &mut io::stdout()) {
+        eprintln!("ERROR: {}", err);
+        std::process::exit(1);
+    }
+}
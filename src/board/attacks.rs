@@ -0,0 +1,450 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::components::{Location, PieceColor};
+use std::sync::OnceLock;
+
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const KING_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+/// Magic numbers for rook attack lookups, indexed by square.
+/// Found by trying random sparse numbers until all blocker subsets map without destructive collisions.
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080_0040_0880_1020,
+    0x0840_0920_02c0_3000,
+    0x1900_2000_1040_0900,
+    0x0880_1000_0800_0480,
+    0x4200_1004_2008_0200,
+    0x8100_0201_0008_0400,
+    0x0200_0401_1088_6200,
+    0x0200_0080_4022_0411,
+    0x0404_8000_8440_0220,
+    0x0000_4010_0040_2000,
+    0x0086_0010_8122_0440,
+    0x0408_8008_0010_0280,
+    0x000a_0012_0104_0820,
+    0x8848_8002_0084_0080,
+    0x4001_0001_0004_0200,
+    0x0442_0001_0210_5084,
+    0x9080_0100_2080_4100,
+    0x0040_4040_0020_1009,
+    0x0000_8080_1000_2009,
+    0x2200_0900_21d0_0100,
+    0x0008_0080_0804_0080,
+    0x0004_0040_0201_0040,
+    0x0011_0400_0801_5042,
+    0x0000_0a00_0176_8104,
+    0x0000_8000_8020_4009,
+    0x2010_0041_4000_2001,
+    0x9800_2002_8010_0080,
+    0x1000_1000_8008_0080,
+    0x0442_000a_0004_9020,
+    0x2100_0400_8002_0080,
+    0x0800_1204_0090_0148,
+    0x0010_040a_0012_8541,
+    0x2800_8040_0080_0030,
+    0x1010_0020_0040_0041,
+    0x4000_2000_1100_4100,
+    0x0610_0084_1080_0800,
+    0x0400_8024_0280_0800,
+    0xc100_0200_8080_0400,
+    0x0002_0008_0200_0401,
+    0x0182_0858_8200_0401,
+    0x0220_2040_0080_8000,
+    0x2860_1000_4002_4022,
+    0x0001_0020_0411_0040,
+    0x9910_1042_000a_0020,
+    0x0004_0800_0400_8080,
+    0x0010_0400_0200_8080,
+    0x2012_0048_8102_0004,
+    0x8300_8424_4482_0011,
+    0x0088_4038_8201_0200,
+    0x0820_4000_8021_0100,
+    0x0110_9100_40a0_0300,
+    0x0801_1002_8008_0480,
+    0x0242_0090_0820_0600,
+    0x1002_0004_8950_0200,
+    0x0040_8002_0001_0080,
+    0x0091_8000_4100_0080,
+    0x0000_2093_0048_8001,
+    0x04c1_0024_1482_4001,
+    0x0200_2000_0b00_1041,
+    0x7000_1000_0420_0901,
+    0x8002_0020_0410_0802,
+    0x3001_0002_084c_0007,
+    0x0888_2218_0081_3004,
+    0x4000_0028_4084_0112,
+];
+
+/// Magic numbers for bishop attack lookups, indexed by square
+const BISHOP_MAGICS: [u64; 64] = [
+    0xa010_0411_0800_3100,
+    0x0060_8202_0a00_2900,
+    0x6810_0106_1920_0000,
+    0x0828_1a05_2000_0408,
+    0x0001_1040_0100_0400,
+    0x0018_9010_0804_8400,
+    0x0004_0a02_1024_5280,
+    0x0002_0021_0808_a402,
+    0x9140_0484_1082_1200,
+    0x0800_0910_1082_0041,
+    0x2050_4804_8322_02c0,
+    0x0100_0914_0108_1000,
+    0x8021_0111_4000_0012,
+    0x0810_0208_0445_0400,
+    0x208b_0542_1090_08a2,
+    0x0080_084a_0804_0204,
+    0x0040_e2a8_0811_244c,
+    0x2505_0220_0800_8108,
+    0x0430_2201_0042_0040,
+    0x010a_0404_2022_0040,
+    0x1105_0002_9040_0000,
+    0x0093_0012_0082_2120,
+    0x4000_a620_4804_3004,
+    0x2801_2004_8a01_5004,
+    0x0060_9000_2a02_0814,
+    0x4404_2000_2408_00d0,
+    0x0110_2800_040a_4400,
+    0x1004_0800_8022_0040,
+    0x0001_0010_1100_4024,
+    0x0010_0440_0080_5040,
+    0x0914_0412_0082_0100,
+    0x0004_8210_1282_1480,
+    0x0024_0405_00c0_5021,
+    0x0088_6110_0208_0200,
+    0x0116_080a_0004_0020,
+    0x4000_0200_8008_0080,
+    0x2450_4501_4084_0040,
+    0x0000_8802_0148_4100,
+    0x0222_0204_0402_0092,
+    0x8081_1106_0000_2e00,
+    0x2842_1011_0500_0801,
+    0x1100_8090_0800_1025,
+    0x0002_0202_221c_0400,
+    0x0422_0140_2200_9020,
+    0x0210_0461_0210_0c00,
+    0xc004_0080_8202_9102,
+    0x00aa_4618_0110_1200,
+    0x0404_0800_8020_1108,
+    0x0205_4210_8c20_5002,
+    0x0410_5448_0410_0100,
+    0x0040_9108_4110_0000,
+    0x0400_2000_4202_1100,
+    0x0000_4204_8504_00c0,
+    0x0200_1004_10a4_2102,
+    0x1040_0208_0121_0102,
+    0x0805_0404_1042_0000,
+    0x2884_8041_3010_0200,
+    0x800c_2622_0124_2000,
+    0x1058_0001_9410_8800,
+    0x0014_2210_5442_0204,
+    0x0104_0000_12a0_2200,
+    0x0200_8810_0330_0100,
+    0x0140_4002_0284_0100,
+    0x0402_0208_0101_0201,
+];
+const ROOK_DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// Returns all squares a knight on the given location attacks
+pub fn knight_attacks(location: &Location) -> Bitboard {
+    tables().knight[location.index()]
+}
+
+/// Returns all squares a king on the given location attacks
+pub fn king_attacks(location: &Location) -> Bitboard {
+    tables().king[location.index()]
+}
+
+/// Returns the diagonally forward squares a pawn of the given color on the given location attacks
+pub fn pawn_attacks(color: &PieceColor, location: &Location) -> Bitboard {
+    match color {
+        PieceColor::White => tables().white_pawn[location.index()],
+        PieceColor::Black => tables().black_pawn[location.index()],
+    }
+}
+
+/// Returns all squares a rook on the given location attacks, including the first blocker in each direction
+pub fn rook_attacks(location: &Location, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.rook[location.index()].attacks(&tables.sliding, occupied)
+}
+
+/// Returns all squares a bishop on the given location attacks, including the first blocker in each direction
+pub fn bishop_attacks(location: &Location, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.bishop[location.index()].attacks(&tables.sliding, occupied)
+}
+
+/// Returns all squares a queen on the given location attacks, including the first blocker in each direction
+pub fn queen_attacks(location: &Location, occupied: Bitboard) -> Bitboard {
+    rook_attacks(location, occupied) | bishop_attacks(location, occupied)
+}
+
+/// Precomputed attack sets, indexed by square
+struct AttackTables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    white_pawn: [Bitboard; 64],
+    black_pawn: [Bitboard; 64],
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    /// The attack sets of all rook and bishop magics, each magic owns a slice starting at its offset
+    sliding: Vec<Bitboard>,
+}
+
+/// Maps the relevant blockers of a sliding piece on one square to an index into its attack slice
+struct Magic {
+    /// The squares whose occupancy affects the attacks, board edges excluded
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn attacks(&self, table: &[Bitboard], occupied: Bitboard) -> Bitboard {
+        let index = ((occupied.0 & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+        table[self.offset + index]
+    }
+}
+
+/// Returns the attack tables, they are computed once on first use
+fn tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(AttackTables::new)
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let white_pawn = [(-1, 1), (1, 1)];
+        let black_pawn = [(-1, -1), (1, -1)];
+        let mut sliding = Vec::new();
+        let rook = (0..64)
+            .map(|index| init_magic(index, ROOK_MAGICS[index], &ROOK_DIRECTIONS, &mut sliding))
+            .collect();
+        let bishop = (0..64)
+            .map(|index| {
+                init_magic(
+                    index,
+                    BISHOP_MAGICS[index],
+                    &BISHOP_DIRECTIONS,
+                    &mut sliding,
+                )
+            })
+            .collect();
+        Self {
+            knight: std::array::from_fn(|index| leaper_attacks(index, &KNIGHT_OFFSETS)),
+            king: std::array::from_fn(|index| leaper_attacks(index, &KING_OFFSETS)),
+            white_pawn: std::array::from_fn(|index| leaper_attacks(index, &white_pawn)),
+            black_pawn: std::array::from_fn(|index| leaper_attacks(index, &black_pawn)),
+            rook,
+            bishop,
+            sliding,
+        }
+    }
+}
+
+/// Returns all squares reached by applying the given offsets to the square with the given index
+fn leaper_attacks(index: usize, offsets: &[(isize, isize)]) -> Bitboard {
+    let location = Location::from_index(index);
+    offsets
+        .iter()
+        .filter_map(|(x, y)| location.translate(*x, *y))
+        .fold(Bitboard::EMPTY, |attacks, target| {
+            attacks | Bitboard::from_location(&target)
+        })
+}
+
+/// Walks the given directions from the square with the given index until a blocker or the board edge.
+/// Used to fill the magic tables, lookups go through `Magic::attacks`.
+fn sliding_attacks(index: usize, directions: &[(isize, isize)], occupied: u64) -> Bitboard {
+    let mut attacks = Bitboard::EMPTY;
+    for (x, y) in directions {
+        let mut location = Location::from_index(index);
+        while let Some(target) = location.translate(*x, *y) {
+            attacks |= Bitboard::from_location(&target);
+            if occupied & (1 << target.index()) != 0 {
+                break;
+            }
+            location = target;
+        }
+    }
+    attacks
+}
+
+/// Returns the squares whose occupancy affects the sliding attacks from the square with the given index,
+/// the last square of each ray is left out since it is attacked regardless of whether it is occupied
+fn relevant_blockers(index: usize, directions: &[(isize, isize)]) -> u64 {
+    let mut mask = 0;
+    for (x, y) in directions {
+        let mut location = Location::from_index(index);
+        while let Some(target) = location.translate(*x, *y) {
+            if target.translate(*x, *y).is_none() {
+                break;
+            }
+            mask |= 1 << target.index();
+            location = target;
+        }
+    }
+    mask
+}
+
+/// Fills the attack sets of all blocker subsets of the square with the given index
+/// into `table` and returns the magic to look them up
+fn init_magic(
+    index: usize,
+    magic: u64,
+    directions: &[(isize, isize)],
+    table: &mut Vec<Bitboard>,
+) -> Magic {
+    let mask = relevant_blockers(index, directions);
+    let bits = mask.count_ones();
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: table.len(),
+    };
+    table.resize(magic.offset + (1 << bits), Bitboard::EMPTY);
+
+    // enumerate all subsets of the mask with the carry-rippler trick
+    let mut blockers = 0u64;
+    loop {
+        let attacks = sliding_attacks(index, directions, blockers);
+        let entry = magic.offset + (blockers.wrapping_mul(magic.magic) >> magic.shift) as usize;
+        // different subsets may only share an entry if they result in the same attacks
+        assert!(
+            table[entry].is_empty() || table[entry] == attacks,
+            "magic for square {} has a destructive collision",
+            index
+        );
+        table[entry] = attacks;
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            break;
+        }
+    }
+    magic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(square: &str) -> Location {
+        Location::from_notation(square).unwrap()
+    }
+
+    fn squares(squares: &[&str]) -> Bitboard {
+        squares.iter().fold(Bitboard::EMPTY, |bitboard, square| {
+            bitboard | Bitboard::from_location(&location(square))
+        })
+    }
+
+    #[test]
+    fn attacks_with_leapers() {
+        assert_eq!(knight_attacks(&location("a1")), squares(&["b3", "c2"]));
+        assert_eq!(knight_attacks(&location("h8")), squares(&["f7", "g6"]));
+        assert_eq!(
+            knight_attacks(&location("d4")),
+            squares(&["b3", "b5", "c2", "c6", "e2", "e6", "f3", "f5"])
+        );
+        assert_eq!(king_attacks(&location("a1")), squares(&["a2", "b1", "b2"]));
+        assert_eq!(
+            king_attacks(&location("h5")),
+            squares(&["g4", "g5", "g6", "h4", "h6"])
+        );
+        assert_eq!(king_attacks(&location("e4")).count(), 8);
+
+        let white = PieceColor::White;
+        let black = PieceColor::Black;
+        assert_eq!(
+            pawn_attacks(&white, &location("e2")),
+            squares(&["d3", "f3"])
+        );
+        assert_eq!(pawn_attacks(&white, &location("a2")), squares(&["b3"]));
+        assert_eq!(pawn_attacks(&black, &location("h7")), squares(&["g6"]));
+        assert_eq!(
+            pawn_attacks(&black, &location("d4")),
+            squares(&["c3", "e3"])
+        );
+        assert!(pawn_attacks(&white, &location("c8")).is_empty());
+    }
+
+    #[test]
+    fn attacks_with_sliders_on_empty_board() {
+        let empty = Bitboard::EMPTY;
+        assert_eq!(rook_attacks(&location("a1"), empty).count(), 14);
+        assert_eq!(rook_attacks(&location("e4"), empty).count(), 14);
+        assert_eq!(
+            bishop_attacks(&location("h8"), empty),
+            squares(&["a1", "b2", "c3", "d4", "e5", "f6", "g7"])
+        );
+        assert_eq!(bishop_attacks(&location("d4"), empty).count(), 13);
+        assert_eq!(bishop_attacks(&location("a4"), empty).count(), 7);
+        assert_eq!(queen_attacks(&location("d4"), empty).count(), 27);
+        assert_eq!(queen_attacks(&location("a1"), empty).count(), 21);
+    }
+
+    #[test]
+    fn attacks_with_blocked_sliders() {
+        assert_eq!(
+            rook_attacks(&location("a1"), squares(&["a4", "d1", "h8"])),
+            squares(&["a2", "a3", "a4", "b1", "c1", "d1"])
+        );
+        assert_eq!(
+            bishop_attacks(&location("d4"), squares(&["f6", "b2", "c5"])),
+            squares(&["c5", "e5", "f6", "c3", "b2", "e3", "f2", "g1"])
+        );
+        assert_eq!(
+            queen_attacks(&location("d1"), squares(&["d2", "c1", "e2", "c2", "g1"])),
+            squares(&["c1", "c2", "d2", "e2", "e1", "f1", "g1"])
+        );
+        // blockers on the board edge and the own square make no difference
+        assert_eq!(
+            rook_attacks(&location("e4"), squares(&["e1", "e8", "a4", "h4", "e4"])),
+            rook_attacks(&location("e4"), Bitboard::EMPTY)
+        );
+    }
+
+    #[test]
+    fn looks_up_the_walked_attacks() {
+        // every square with a few blocker sets that are not aligned to the magics
+        let blockers = [
+            0,
+            0x0000_0010_0800_0000,
+            0x00ff_0000_0000_ff00,
+            0x8142_2418_1824_4281,
+            0x1234_5678_9abc_def0,
+        ];
+        for index in 0..64 {
+            let location = Location::from_index(index);
+            for occupied in blockers {
+                assert_eq!(
+                    rook_attacks(&location, Bitboard(occupied)),
+                    sliding_attacks(index, &ROOK_DIRECTIONS, occupied)
+                );
+                assert_eq!(
+                    bishop_attacks(&location, Bitboard(occupied)),
+                    sliding_attacks(index, &BISHOP_DIRECTIONS, occupied)
+                );
+            }
+        }
+    }
+}
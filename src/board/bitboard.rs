@@ -0,0 +1,104 @@
+use crate::board::components::Location;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// A set of squares where bit `n` represents the square with index `n`, so a1 is bit 0 and h8 is bit 63
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    /// Returns a bitboard that only contains the given location
+    pub fn from_location(location: &Location) -> Self {
+        Self::from_index(location.index())
+    }
+
+    /// Returns a bitboard that only contains the square with the given index
+    pub fn from_index(index: usize) -> Self {
+        Self(1 << index)
+    }
+
+    pub fn contains(&self, location: &Location) -> bool {
+        self.0 & (1 << location.index()) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of squares in this set
+    pub fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Returns the location with the lowest index in this set
+    pub fn first(&self) -> Option<Location> {
+        match self.is_empty() {
+            true => None,
+            false => Some(Location::from_index(self.0.trailing_zeros() as usize)),
+        }
+    }
+
+    /// Returns an iterator over all locations in this set, ordered by index
+    pub fn squares(self) -> impl Iterator<Item = Location> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(Location::from_index(index))
+        })
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
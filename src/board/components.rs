@@ -8,7 +8,7 @@ use crate::constants::{
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
 
-#[derive(Component, Inspectable, Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Component, Inspectable, Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Location {
     pub x: usize,
     pub y: usize,
@@ -21,6 +21,27 @@ impl Location {
         Self { x, y }
     }
 
+    /// Parses a square in algebraic notation like `e4`
+    pub fn from_notation(square: &str) -> Option<Self> {
+        match square.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Some(Self::new((file - b'a') as usize, (rank - b'1') as usize))
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates a location from the given square index, where a1 is 0 and h8 is 63
+    pub fn from_index(index: usize) -> Self {
+        assert!(index < 64);
+        Self::new(index % 8, index / 8)
+    }
+
+    /// Returns the square index of this location, where a1 is 0 and h8 is 63
+    pub fn index(&self) -> usize {
+        self.y * 8 + self.x
+    }
+
     /// Translates the current location with the given offsets and returns a new one
     pub fn translate(&self, x_offset: isize, y_offset: isize) -> Option<Location> {
         let new_x = self.x as isize + x_offset;
@@ -48,6 +69,7 @@ impl fmt::Display for Location {
 #[derive(Component, Debug)]
 pub struct Square;
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SquareColor {
     Light,
     Dark,
@@ -120,7 +142,7 @@ impl fmt::Display for File {
 #[derive(Component)]
 pub struct Board;
 
-#[derive(Inspectable, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Inspectable, Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum PieceType {
     King,
     Queen,
@@ -130,12 +152,118 @@ pub enum PieceType {
     Pawn,
 }
 
-#[derive(Inspectable, Debug, Eq, PartialEq, Copy, Clone)]
+impl PieceType {
+    /// All piece types a pawn can be promoted to
+    pub const PROMOTIONS: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+
+    /// All piece types, ordered by their `index`
+    pub const ALL: [PieceType; 6] = [
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    /// Returns a unique index between 0 and 5 used for lookup tables
+    pub fn index(&self) -> usize {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 1,
+            PieceType::Rook => 2,
+            PieceType::Bishop => 3,
+            PieceType::Knight => 4,
+            PieceType::Pawn => 5,
+        }
+    }
+
+    /// Returns the piece type for the given uppercase letter used in SAN
+    pub fn from_notation(letter: char) -> Option<Self> {
+        PieceType::ALL
+            .into_iter()
+            .find(|kind| kind.notation() == letter)
+    }
+
+    pub fn notation(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Rook => 'R',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Pawn => 'P',
+        }
+    }
+}
+
+impl fmt::Display for PieceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            PieceType::King => "King",
+            PieceType::Queen => "Queen",
+            PieceType::Rook => "Rook",
+            PieceType::Bishop => "Bishop",
+            PieceType::Knight => "Knight",
+            PieceType::Pawn => "Pawn",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+#[derive(Inspectable, Debug, Eq, PartialEq, Copy, Clone, Hash)]
 pub enum PieceColor {
     Black,
     White,
 }
 
+impl PieceColor {
+    /// Returns the color of the other side
+    pub fn opponent(&self) -> Self {
+        match self {
+            PieceColor::Black => PieceColor::White,
+            PieceColor::White => PieceColor::Black,
+        }
+    }
+
+    /// Returns a unique index between 0 and 1 used for lookup tables
+    pub fn index(&self) -> usize {
+        match self {
+            PieceColor::Black => 0,
+            PieceColor::White => 1,
+        }
+    }
+
+    /// Returns the rank index where the king and rooks of this color start
+    pub fn home_rank(&self) -> usize {
+        match self {
+            PieceColor::Black => 7,
+            PieceColor::White => 0,
+        }
+    }
+
+    /// Returns the rank index where the pawns of this color start
+    pub fn pawn_rank(&self) -> usize {
+        match self {
+            PieceColor::Black => 6,
+            PieceColor::White => 1,
+        }
+    }
+
+    /// Returns the rank offset for a single pawn step of this color
+    pub fn pawn_direction(&self) -> isize {
+        match self {
+            PieceColor::Black => -1,
+            PieceColor::White => 1,
+        }
+    }
+}
+
 impl fmt::Display for PieceColor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let color = match self {
@@ -146,7 +274,7 @@ impl fmt::Display for PieceColor {
     }
 }
 
-#[derive(Component, Inspectable, Debug, Copy, Clone)]
+#[derive(Component, Inspectable, Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Piece {
     pub kind: PieceType,
     pub color: PieceColor,
@@ -163,14 +291,7 @@ impl Piece {
     }
 
     pub fn notation(&self) -> char {
-        match self.kind {
-            PieceType::King => 'K',
-            PieceType::Queen => 'Q',
-            PieceType::Rook => 'R',
-            PieceType::Bishop => 'B',
-            PieceType::Knight => 'N',
-            PieceType::Pawn => 'P',
-        }
+        self.kind.notation()
     }
 
     /// Returns the identifier for the `PieceTheme` resource
@@ -189,6 +310,72 @@ impl fmt::Display for Piece {
     }
 }
 
+/// The side of the board a king castles to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CastlingSide {
+    Kingside,
+    Queenside,
+}
+
+impl CastlingSide {
+    /// Returns the file index of the rook before castling
+    pub fn rook_source_file(&self) -> usize {
+        match self {
+            CastlingSide::Kingside => 7,
+            CastlingSide::Queenside => 0,
+        }
+    }
+
+    /// Returns the file index of the rook after castling
+    pub fn rook_target_file(&self) -> usize {
+        match self {
+            CastlingSide::Kingside => 5,
+            CastlingSide::Queenside => 3,
+        }
+    }
+
+    /// Returns the file index of the king after castling
+    pub fn king_target_file(&self) -> usize {
+        match self {
+            CastlingSide::Kingside => 6,
+            CastlingSide::Queenside => 2,
+        }
+    }
+
+    /// Returns the file indices that must be empty between king and rook
+    pub fn empty_files(&self) -> std::ops::Range<usize> {
+        match self {
+            CastlingSide::Kingside => 5..7,
+            CastlingSide::Queenside => 1..4,
+        }
+    }
+
+    /// Returns the file indices the king occupies or passes through while castling,
+    /// none of them are allowed to be attacked
+    pub fn king_path_files(&self) -> std::ops::RangeInclusive<usize> {
+        match self {
+            CastlingSide::Kingside => 4..=6,
+            CastlingSide::Queenside => 2..=4,
+        }
+    }
+
+    /// Returns the castling side for a king move between the given files, if any
+    pub fn from_king_move(source_file: usize, target_file: usize) -> Option<Self> {
+        match (source_file, target_file) {
+            (4, 6) => Some(CastlingSide::Kingside),
+            (4, 2) => Some(CastlingSide::Queenside),
+            _ => None,
+        }
+    }
+
+    pub fn notation(&self) -> &'static str {
+        match self {
+            CastlingSide::Kingside => "O-O",
+            CastlingSide::Queenside => "O-O-O",
+        }
+    }
+}
+
 /// Used to distinguish between selected and non-selected pieces and squares
 #[derive(Component)]
 pub struct Selected;
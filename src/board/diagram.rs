@@ -0,0 +1,330 @@
+use crate::board::components::{Location, PieceColor};
+use crate::board::position::Position;
+use crate::board::utils::square_color;
+use crate::constants::{DIAGRAM_ARROW_COLOR, PIECE_THEME, SQUARE_SIZE};
+use bevy::prelude::Color;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+/// Describes how a position is drawn by `render_svg`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramOptions {
+    /// The color whose pieces start at the bottom of the diagram
+    pub orientation: PieceColor,
+    /// Whether the files and ranks are labeled along the bottom and left edge
+    pub coordinates: bool,
+    /// The source and target square of the last move, which are highlighted
+    pub last_move: Option<(Location, Location)>,
+    /// Arrows pointing from the first to the second square
+    pub arrows: Vec<(Location, Location)>,
+    /// The directory below `assets/piece` the piece images are taken from
+    pub theme: String,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        Self {
+            orientation: PieceColor::White,
+            coordinates: false,
+            last_move: None,
+            arrows: Vec::new(),
+            theme: PIECE_THEME.to_string(),
+        }
+    }
+}
+
+/// Renders the position into a standalone SVG document with the same square colors and pieces
+/// as the board of the app, the piece images are read from `assets/piece/<theme>`
+pub fn render_svg(
+    position: &Position,
+    options: &DiagramOptions,
+    assets: &Path,
+) -> io::Result<String> {
+    let size = SQUARE_SIZE * 8.0;
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+
+    // every piece image is embedded once and placed with `use` on all its squares
+    let pieces = position.pieces();
+    let mut symbols = BTreeMap::new();
+    for (piece, _) in &pieces {
+        if let Entry::Vacant(entry) = symbols.entry(piece.resource_name()) {
+            let path = assets
+                .join("piece")
+                .join(&options.theme)
+                .join(format!("{}.svg", entry.key()));
+            let symbol =
+                piece_symbol(entry.key(), &std::fs::read_to_string(&path)?).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} is not a valid SVG image", path.display()),
+                    )
+                })?;
+            entry.insert(symbol);
+        }
+    }
+    svg.push_str("<defs>");
+    symbols.values().for_each(|symbol| svg.push_str(symbol));
+    svg.push_str("</defs>");
+
+    let highlighted = |location: &Location| {
+        options
+            .last_move
+            .is_some_and(|(from, to)| from == *location || to == *location)
+    };
+    for index in 0..64 {
+        let location = Location::from_index(index);
+        let color = square_color(location.x, location.y);
+        let fill = match highlighted(&location) {
+            true => color.selected(),
+            false => color.default(),
+        };
+        let (x, y) = square_origin(&location, options.orientation);
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}" fill="{}"/>"#,
+            hex(fill)
+        );
+    }
+
+    if options.coordinates {
+        write_coordinates(&mut svg, options);
+    }
+
+    for (piece, location) in &pieces {
+        let (x, y) = square_origin(location, options.orientation);
+        let _ = write!(
+            svg,
+            r##"<use href="#{}" x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}"/>"##,
+            piece.resource_name()
+        );
+    }
+
+    for (from, to) in &options.arrows {
+        write_arrow(&mut svg, from, to, options.orientation);
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Returns the top left corner of the square in the diagram
+fn square_origin(location: &Location, orientation: PieceColor) -> (f32, f32) {
+    let (column, row) = match orientation {
+        PieceColor::White => (location.x, 7 - location.y),
+        PieceColor::Black => (7 - location.x, location.y),
+    };
+    (column as f32 * SQUARE_SIZE, row as f32 * SQUARE_SIZE)
+}
+
+/// Labels the ranks in the top left corner of the left column and the files in the bottom right
+/// corner of the bottom row, each in the color of the other squares to stay readable
+fn write_coordinates(svg: &mut String, options: &DiagramOptions) {
+    let font_size = SQUARE_SIZE * 0.2;
+    let inset = SQUARE_SIZE * 0.05;
+    let (left_file, bottom_rank) = match options.orientation {
+        PieceColor::White => (0, 0),
+        PieceColor::Black => (7, 7),
+    };
+    for i in 0..8 {
+        let rank = Location::new(left_file, i);
+        let (x, y) = square_origin(&rank, options.orientation);
+        let _ = write!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{font_size}" fill="{}">{}</text>"#,
+            x + inset,
+            y + inset + font_size,
+            hex(opposite_square_color(&rank)),
+            i + 1
+        );
+
+        let file = Location::new(i, bottom_rank);
+        let (x, y) = square_origin(&file, options.orientation);
+        let _ = write!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{font_size}" text-anchor="end" fill="{}">{}</text>"#,
+            x + SQUARE_SIZE - inset,
+            y + SQUARE_SIZE - inset,
+            hex(opposite_square_color(&file)),
+            (b'a' + i as u8) as char
+        );
+    }
+}
+
+fn opposite_square_color(location: &Location) -> Color {
+    square_color(location.x + 1, location.y).default()
+}
+
+/// Draws an arrow between the centers of both squares, the shaft stops at the arrow head
+/// so the translucent parts do not overlap
+fn write_arrow(svg: &mut String, from: &Location, to: &Location, orientation: PieceColor) {
+    let center = |location| {
+        let (x, y) = square_origin(location, orientation);
+        (x + SQUARE_SIZE / 2.0, y + SQUARE_SIZE / 2.0)
+    };
+    let (x1, y1) = center(from);
+    let (x2, y2) = center(to);
+    let length = (x2 - x1).hypot(y2 - y1);
+    if length == 0.0 {
+        return;
+    }
+    let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+    let head_length = SQUARE_SIZE * 0.4;
+    let head_width = SQUARE_SIZE * 0.4;
+    let (base_x, base_y) = (x2 - dx * head_length, y2 - dy * head_length);
+    let [.., alpha] = DIAGRAM_ARROW_COLOR.as_rgba_f32();
+    let color = hex(DIAGRAM_ARROW_COLOR);
+    let _ = write!(
+        svg,
+        r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{base_x:.1}" y2="{base_y:.1}" stroke="{color}" stroke-opacity="{alpha}" stroke-width="{:.1}"/>"#,
+        SQUARE_SIZE * 0.15
+    );
+    let _ = write!(
+        svg,
+        r#"<polygon points="{x2:.1},{y2:.1} {:.1},{:.1} {:.1},{:.1}" fill="{color}" fill-opacity="{alpha}"/>"#,
+        base_x - dy * head_width / 2.0,
+        base_y + dx * head_width / 2.0,
+        base_x + dy * head_width / 2.0,
+        base_y - dx * head_width / 2.0,
+    );
+}
+
+/// Formats the RGB part of the color like `#dee3e6`
+fn hex(color: Color) -> String {
+    let [r, g, b, _] = color.as_rgba_f32();
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// Turns a piece image into a `symbol` with the given id.
+/// The ids inside the image are prefixed with it as well, because all images of a theme use the same ids
+/// for their gradients.
+fn piece_symbol(name: &str, image: &str) -> Option<String> {
+    let root_start = image.find("<svg")?;
+    let root_end = root_start + image[root_start..].find('>')?;
+    let content_end = image.rfind("</svg>")?;
+    let root = &image[root_start + "<svg".len()..root_end];
+
+    // keep the presentation attributes like `fill-rule` of the root, but size the symbol by its view box
+    let mut symbol = format!(r#"<symbol id="{}""#, name);
+    for pair in root.split('"').collect::<Vec<_>>().chunks_exact(2) {
+        let attribute = pair[0].trim().trim_end_matches('=');
+        if !matches!(attribute, "width" | "height" | "xmlns" | "version") {
+            let _ = write!(symbol, r#" {}="{}""#, attribute, pair[1]);
+        }
+    }
+    symbol.push('>');
+
+    let mut content = image[root_end + 1..content_end].to_string();
+    let ids: Vec<String> = content
+        .match_indices(r#" id=""#)
+        .filter_map(|(index, pattern)| {
+            let start = index + pattern.len();
+            content[start..]
+                .find('"')
+                .map(|end| content[start..start + end].to_string())
+        })
+        .collect();
+    for id in ids {
+        let prefixed = format!("{}-{}", name, id);
+        content = content
+            .replace(
+                &format!(r#" id="{}""#, id),
+                &format!(r#" id="{}""#, prefixed),
+            )
+            .replace(&format!("url(#{})", id), &format!("url(#{})", prefixed))
+            .replace(
+                &format!(r##"href="#{}""##, id),
+                &format!(r##"href="#{}""##, prefixed),
+            );
+    }
+    symbol.push_str(&content);
+    symbol.push_str("</symbol>");
+    Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+
+    fn render(fen: &str, options: &DiagramOptions) -> String {
+        let position = Position::from_fen(fen).unwrap();
+        render_svg(
+            &position,
+            options,
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn renders_all_squares_and_pieces() {
+        let svg = render(INITIAL_FEN, &DiagramOptions::default());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(svg.matches("<use").count(), 32);
+        assert_eq!(svg.matches("<symbol").count(), 12);
+        assert_eq!(svg.matches("<text").count(), 0);
+
+        // the gradients of the pieces must not share their ids
+        assert!(svg.contains(r#" id="wK-a""#));
+        assert!(svg.contains("url(#bQ-a)"));
+        assert!(!svg.contains(r#" id="a""#));
+        // a1 is a dark square in the bottom left corner
+        assert!(svg.contains(&format!(
+            r#"<rect x="0" y="560" width="80" height="80" fill="{}"/>"#,
+            hex(crate::constants::SQUARE_COLOR_DARK_DEFAULT)
+        )));
+        assert!(svg.contains(r##"<use href="#wR" x="0" y="560""##));
+    }
+
+    #[test]
+    fn renders_options() {
+        let e2 = Location::from_notation("e2").unwrap();
+        let e4 = Location::from_notation("e4").unwrap();
+        let options = DiagramOptions {
+            orientation: PieceColor::Black,
+            coordinates: true,
+            last_move: Some((e2, e4)),
+            arrows: vec![(
+                Location::from_notation("g8").unwrap(),
+                Location::from_notation("f6").unwrap(),
+            )],
+            ..Default::default()
+        };
+        let svg = render(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            &options,
+        );
+        // the piece images are embedded in front of the board
+        let (_, board) = svg.split_once("</defs>").unwrap();
+        assert_eq!(board.matches("<text").count(), 16);
+        assert_eq!(board.matches("<line").count(), 1);
+        assert_eq!(board.matches("<polygon").count(), 1);
+        // h8 is in the bottom left corner and e4 is highlighted
+        assert!(svg.contains(r##"<use href="#bR" x="0" y="560""##));
+        assert!(svg.contains(&format!(
+            r#"<rect x="240" y="240" width="80" height="80" fill="{}"/>"#,
+            hex(crate::constants::SQUARE_COLOR_LIGHT_SELECTED)
+        )));
+    }
+
+    #[test]
+    fn reports_missing_theme() {
+        let options = DiagramOptions {
+            theme: "missing".to_string(),
+            ..Default::default()
+        };
+        let position = Position::from_fen(INITIAL_FEN).unwrap();
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        assert!(render_svg(&position, &options, &assets).is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use crate::board::fen::FenError;
+use crate::board::position::{Move, Position};
+use crate::board::san::SanError;
+use crate::board::search::SearchResult;
+use std::fmt;
+
+/// An operation of an EPD record like `bm Nf3;` or `id "WAC.001";`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    /// The operands without the quotes of string operands
+    pub operands: Vec<String>,
+}
+
+/// A position of an EPD file together with its operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// The position, its clocks are taken from the `hmvc` and `fmvn` operations if present
+    pub position: Position,
+    /// All operations in the order they appear
+    pub operations: Vec<EpdOperation>,
+    /// The moves of the `bm` operation, one of them has to be found by the engine
+    pub best_moves: Vec<Move>,
+    /// The moves of the `am` operation, none of them may be played by the engine
+    pub avoid_moves: Vec<Move>,
+}
+
+/// Describes why an EPD record could not be read
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EpdError {
+    /// The four position fields do not describe a valid position
+    InvalidPosition(FenError),
+    /// The operation with the given opcode is malformed
+    MalformedOperation(String),
+    /// A string operand is not closed
+    UnterminatedString,
+    /// A move operand of `bm` or `am` is not a legal move in SAN
+    InvalidMove {
+        opcode: String,
+        token: String,
+        error: SanError,
+    },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::InvalidPosition(err) => write!(f, "invalid position: {}", err),
+            EpdError::MalformedOperation(opcode) => write!(f, "malformed operation '{}'", opcode),
+            EpdError::UnterminatedString => write!(f, "missing closing '\"'"),
+            EpdError::InvalidMove {
+                opcode,
+                token,
+                error,
+            } => write!(f, "{} '{}' in operation '{}'", error, token, opcode),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+impl EpdRecord {
+    /// Parses a single EPD line
+    pub fn parse(line: &str) -> Result<Self, EpdError> {
+        let line = line.trim();
+        let mut fields = Vec::new();
+        let mut rest = line;
+        while fields.len() < 4 {
+            let field_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if field_end == 0 {
+                break;
+            }
+            fields.push(&rest[..field_end]);
+            rest = rest[field_end..].trim_start();
+        }
+        let mut position =
+            Position::from_fen(&fields.join(" ")).map_err(EpdError::InvalidPosition)?;
+
+        let operations = parse_operations(rest)?;
+        let mut best_moves = Vec::new();
+        let mut avoid_moves = Vec::new();
+        for operation in &operations {
+            match operation.opcode.as_str() {
+                "bm" => best_moves = parse_moves(&position, operation)?,
+                "am" => avoid_moves = parse_moves(&position, operation)?,
+                "hmvc" => position.halfmove_clock = parse_number(operation)?,
+                "fmvn" => position.fullmove_number = parse_number(operation)?,
+                _ => {}
+            }
+        }
+        Ok(Self {
+            position,
+            operations,
+            best_moves,
+            avoid_moves,
+        })
+    }
+
+    /// Returns the operation with the given opcode
+    pub fn operation(&self, opcode: &str) -> Option<&EpdOperation> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+    }
+
+    /// Returns the first operand of the `id` operation
+    pub fn id(&self) -> Option<&str> {
+        self.first_operand("id")
+    }
+
+    /// Returns the first operand of the `c0` comment operation
+    pub fn comment(&self) -> Option<&str> {
+        self.first_operand("c0")
+    }
+
+    /// Returns the number of moves of the `dm` operation in which the side to move mates
+    pub fn direct_mate(&self) -> Option<u32> {
+        self.first_operand("dm")?.parse().ok()
+    }
+
+    /// Returns true if the record tells which moves are good or bad or in how many moves to mate
+    pub fn has_test(&self) -> bool {
+        !self.best_moves.is_empty() || !self.avoid_moves.is_empty() || self.direct_mate().is_some()
+    }
+
+    /// Returns true if the engine has chosen one of the best moves and none of the moves to avoid,
+    /// and has found the mate of the `dm` operation
+    pub fn is_solved_by(&self, answer: &EngineAnswer) -> bool {
+        let mv = match answer.best_move {
+            Some(mv) => mv,
+            None => return false,
+        };
+        (self.best_moves.is_empty() || self.best_moves.contains(&mv))
+            && !self.avoid_moves.contains(&mv)
+            && self
+                .direct_mate()
+                .is_none_or(|moves| answer.mate == Some(moves))
+    }
+
+    fn first_operand(&self, opcode: &str) -> Option<&str> {
+        self.operation(opcode)?.operands.first().map(String::as_str)
+    }
+}
+
+/// Reads all records of the given EPD text, empty lines are skipped.
+/// The error holds the line number of the first record that could not be read.
+pub fn read_epd(text: &str) -> Result<Vec<EpdRecord>, (usize, EpdError)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| EpdRecord::parse(line).map_err(|err| (number + 1, err)))
+        .collect()
+}
+
+/// Splits the operations after the position fields, each of them is terminated by a semicolon
+fn parse_operations(text: &str) -> Result<Vec<EpdOperation>, EpdError> {
+    let mut operations = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(operations);
+        }
+        let opcode: String =
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != ';')).collect();
+        let valid = opcode.starts_with(|c: char| c.is_ascii_alphabetic())
+            && opcode
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(EpdError::MalformedOperation(opcode));
+        }
+
+        let mut operands = Vec::new();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next() {
+                Some(';') => break,
+                Some('"') => {
+                    let operand: String =
+                        std::iter::from_fn(|| chars.next_if(|c| *c != '"')).collect();
+                    if chars.next().is_none() {
+                        return Err(EpdError::UnterminatedString);
+                    }
+                    operands.push(operand);
+                }
+                Some(c) => {
+                    let rest =
+                        std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != ';'));
+                    operands.push(std::iter::once(c).chain(rest).collect());
+                }
+                None => return Err(EpdError::MalformedOperation(opcode)),
+            }
+        }
+        operations.push(EpdOperation { opcode, operands });
+    }
+}
+
+fn parse_moves(position: &Position, operation: &EpdOperation) -> Result<Vec<Move>, EpdError> {
+    operation
+        .operands
+        .iter()
+        .map(|token| {
+            position
+                .parse_san(token)
+                .map_err(|error| EpdError::InvalidMove {
+                    opcode: operation.opcode.clone(),
+                    token: token.clone(),
+                    error,
+                })
+        })
+        .collect()
+}
+
+fn parse_number(operation: &EpdOperation) -> Result<usize, EpdError> {
+    match operation.operands.as_slice() {
+        [number] => number
+            .parse()
+            .map_err(|_| EpdError::MalformedOperation(operation.opcode.clone())),
+        _ => Err(EpdError::MalformedOperation(operation.opcode.clone())),
+    }
+}
+
+/// The result of an engine search on the position of a record
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct EngineAnswer {
+    pub best_move: Option<Move>,
+    /// The number of moves in which the side to move mates, if the engine has found a mate
+    pub mate: Option<u32>,
+}
+
+impl From<&SearchResult> for EngineAnswer {
+    fn from(result: &SearchResult) -> Self {
+        Self {
+            best_move: result.best_move,
+            mate: result.mate_in().and_then(|moves| u32::try_from(moves).ok()),
+        }
+    }
+}
+
+/// The outcome of running an engine on the records of a test suite
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SuiteReport {
+    pub solved: usize,
+    /// The number of records with a `bm`, `am` or `dm` operation
+    pub total: usize,
+    /// The index of every failed record and the answer of the engine
+    pub failed: Vec<(usize, EngineAnswer)>,
+}
+
+/// Runs `search` on every record that tells which moves are good or bad or in how many moves to mate
+/// and counts how many of them the engine solves
+pub fn run_suite(
+    records: &[EpdRecord],
+    mut search: impl FnMut(&EpdRecord) -> EngineAnswer,
+) -> SuiteReport {
+    let mut report = SuiteReport::default();
+    for (index, record) in records.iter().enumerate() {
+        if !record.has_test() {
+            continue;
+        }
+        report.total += 1;
+        let answer = search(record);
+        if record.is_solved_by(&answer) {
+            report.solved += 1;
+        } else {
+            report.failed.push((index, answer));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::components::Location;
+
+    const SUITE: &str = r#"
+2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm Rxb2; id "WAC.002"; c0 "rook; takes";
+5rk1/1ppb3p/p1pb4/6q1/3P1p1r/2P1R2P/PP1BQ1P1/5RKN w - - am Rxf4 Re4; hmvc 3; fmvn 21;
+r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - dm 3; id "mate";
+"#;
+
+    #[test]
+    fn reads_operations() {
+        let records = read_epd(SUITE).unwrap();
+        assert_eq!(records.len(), 4);
+
+        let first = &records[0];
+        assert_eq!(first.id(), Some("WAC.001"));
+        assert_eq!(
+            first.best_moves,
+            vec![Move::new(Location::new(6, 2), Location::new(6, 5), None)]
+        );
+        assert_eq!(records[1].comment(), Some("rook; takes"));
+
+        let third = &records[2];
+        assert_eq!(third.avoid_moves.len(), 2);
+        assert_eq!(third.position.halfmove_clock, 3);
+        assert_eq!(third.position.fullmove_number, 21);
+
+        let mate = &records[3];
+        assert_eq!(mate.direct_mate(), Some(3));
+        assert!(mate.has_test());
+        assert_eq!(
+            mate.operation("dm")
+                .map(|operation| operation.operands.clone()),
+            Some(vec!["3".to_string()])
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_line() {
+        let line = "8/8/8/8/8/8/8/k3K3 w - -";
+        assert_eq!(
+            read_epd(&format!("{} id \"a\";\n{} bm Kb1;", line, line)),
+            Err((
+                2,
+                EpdError::InvalidMove {
+                    opcode: "bm".to_string(),
+                    token: "Kb1".to_string(),
+                    error: SanError::Illegal,
+                }
+            ))
+        );
+        assert_eq!(
+            EpdRecord::parse(&format!("{} id \"a;", line)),
+            Err(EpdError::UnterminatedString)
+        );
+        assert_eq!(
+            EpdRecord::parse(&format!("{} id \"a\"", line)),
+            Err(EpdError::MalformedOperation("id".to_string()))
+        );
+        assert_eq!(
+            EpdRecord::parse(&format!("{} hmvc x;", line)),
+            Err(EpdError::MalformedOperation("hmvc".to_string()))
+        );
+        assert!(matches!(
+            EpdRecord::parse("8/8/8/8/8/8/8/k3K3 w"),
+            Err(EpdError::InvalidPosition(_))
+        ));
+    }
+
+    #[test]
+    fn counts_solved_records() {
+        let records = read_epd(SUITE).unwrap();
+        let report = run_suite(&records, |record| {
+            let best_move = record.best_moves.first().copied().or_else(|| {
+                // play the move to avoid for the third record and any move in the mate record
+                record.position.parse_san("Rxf4").ok()
+            });
+            EngineAnswer {
+                best_move: best_move.or_else(|| record.position.legal_moves().first().copied()),
+                mate: Some(2),
+            }
+        });
+        assert_eq!(report.total, 4);
+        assert_eq!(report.solved, 2);
+        let failed: Vec<_> = report.failed.iter().map(|(index, _)| *index).collect();
+        assert_eq!(failed, [2, 3]);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::board::components::{Location, PieceColor, PieceType};
+use crate::board::position::Position;
+
+/// The game phase with all pieces on the board, it drops to 0 when only kings and pawns are left
+const MAX_PHASE: i32 = 24;
+
+// The piece-square tables are written from white's view with a8 in the top left corner
+// and h1 in the bottom right corner, black pieces use the vertically mirrored square.
+
+#[rustfmt::skip]
+const PAWN_MIDDLEGAME: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Returns the value of a piece in centipawns, the king is not counted as it cannot be traded
+pub fn piece_value(kind: PieceType) -> i32 {
+    match kind {
+        PieceType::King => 0,
+        PieceType::Queen => 900,
+        PieceType::Rook => 500,
+        PieceType::Bishop => 330,
+        PieceType::Knight => 320,
+        PieceType::Pawn => 100,
+    }
+}
+
+/// Returns the middlegame and endgame value of a piece in centipawns,
+/// minor pieces lose value in the endgame while rooks and pawns gain
+fn material(kind: PieceType) -> (i32, i32) {
+    match kind {
+        PieceType::King => (0, 0),
+        PieceType::Queen => (900, 920),
+        PieceType::Rook => (480, 520),
+        PieceType::Bishop => (335, 305),
+        PieceType::Knight => (325, 290),
+        PieceType::Pawn => (85, 100),
+    }
+}
+
+/// Returns how much the piece counts towards the middlegame
+fn phase_weight(kind: PieceType) -> i32 {
+    match kind {
+        PieceType::Queen => 4,
+        PieceType::Rook => 2,
+        PieceType::Bishop | PieceType::Knight => 1,
+        PieceType::King | PieceType::Pawn => 0,
+    }
+}
+
+/// Returns the middlegame and endgame bonus of a piece on the given square
+fn piece_square(kind: PieceType, color: PieceColor, location: &Location) -> (i32, i32) {
+    let index = match color {
+        PieceColor::White => (7 - location.y) * 8 + location.x,
+        PieceColor::Black => location.y * 8 + location.x,
+    };
+    match kind {
+        PieceType::King => (KING_MIDDLEGAME[index], KING_ENDGAME[index]),
+        PieceType::Queen => (QUEEN[index], QUEEN[index]),
+        PieceType::Rook => (ROOK[index], ROOK[index]),
+        PieceType::Bishop => (BISHOP[index], BISHOP[index]),
+        PieceType::Knight => (KNIGHT[index], KNIGHT[index]),
+        PieceType::Pawn => (PAWN_MIDDLEGAME[index], PAWN_ENDGAME[index]),
+    }
+}
+
+/// Evaluates the position in centipawns from the view of the side to move.
+/// The material and piece-square scores of the middlegame and the endgame are blended
+/// by the game phase, which is derived from the pieces left on the board.
+pub fn evaluate(position: &Position) -> i32 {
+    let (mut middlegame, mut endgame, mut phase) = (0, 0, 0);
+    for (piece, location) in position.pieces() {
+        let (material_mg, material_eg) = material(piece.kind);
+        let (square_mg, square_eg) = piece_square(piece.kind, piece.color, &location);
+        let sign = match piece.color {
+            PieceColor::White => 1,
+            PieceColor::Black => -1,
+        };
+        middlegame += sign * (material_mg + square_mg);
+        endgame += sign * (material_eg + square_eg);
+        phase += phase_weight(piece.kind);
+    }
+    // promotions can raise the phase above the one of the initial position
+    let phase = phase.min(MAX_PHASE);
+    let score = (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE;
+    match position.side_to_move {
+        PieceColor::White => score,
+        PieceColor::Black => -score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+
+    const POSITIONS: [&str; 4] = [
+        INITIAL_FEN,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ];
+
+    fn evaluate_fen(fen: &str) -> i32 {
+        evaluate(&Position::from_fen(fen).unwrap())
+    }
+
+    /// Mirrors the board vertically and swaps the colors of all pieces and castling rights,
+    /// the side to move is kept unless `swap_side` is set
+    fn flip_colors(fen: &str, swap_side: bool) -> String {
+        let swap_case = |text: &str| -> String {
+            text.chars()
+                .map(|c| match c.is_ascii_uppercase() {
+                    true => c.to_ascii_lowercase(),
+                    false => c.to_ascii_uppercase(),
+                })
+                .collect()
+        };
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let board: Vec<&str> = fields[0].split('/').rev().collect();
+        let side = match (fields[1], swap_side) {
+            ("w", true) => "b",
+            (_, true) => "w",
+            (side, false) => side,
+        };
+        format!(
+            "{} {} {} - {} {}",
+            swap_case(&board.join("/")),
+            side,
+            swap_case(fields[2]),
+            fields[4],
+            fields[5]
+        )
+    }
+
+    #[test]
+    fn is_color_symmetric() {
+        assert_eq!(evaluate_fen(INITIAL_FEN), 0);
+        for fen in POSITIONS {
+            let score = evaluate_fen(fen);
+            assert_eq!(evaluate_fen(&flip_colors(fen, false)), -score, "{}", fen);
+            // from the view of the other side to move the flipped position is the same
+            assert_eq!(evaluate_fen(&flip_colors(fen, true)), score, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn tapers_between_middlegame_and_endgame() {
+        // with all pieces on the board the king is safer in the corner
+        let castled = evaluate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BKR w kq - 0 1");
+        let center = evaluate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKB1R w kq - 0 1");
+        assert!(castled > center);
+
+        // without pieces it belongs in the center
+        let corner = evaluate_fen("4k3/pppp4/8/8/8/8/PPPP4/6K1 w - - 0 1");
+        let center = evaluate_fen("4k3/pppp4/8/8/3K4/8/PPPP4/8 w - - 0 1");
+        assert!(center > corner);
+    }
+
+    #[test]
+    fn prefers_active_pieces() {
+        // black is to move, so the better white knight lowers the score
+        let developed = evaluate_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1");
+        let rim = evaluate_fen("rnbqkbnr/pppppppp/8/8/8/7N/PPPPPPPP/RNBQKB1R b KQkq - 1 1");
+        assert!(developed < rim);
+    }
+}
@@ -1,14 +1,31 @@
-use crate::board::{PlayedMove, SelectedPiece};
+use crate::board::components::PieceType;
+use crate::board::pgn::PgnGame;
+use crate::board::position::{Move, Position};
+use crate::board::{GameResult, PlayedMove, SelectedPiece};
 use bevy::prelude::*;
 
 pub struct UncheckedPieceMoveEvent {
     pub selected: SelectedPiece,
     pub target: Entity,
+    /// The piece a pawn should be promoted to when reaching the last rank
+    pub promotion: Option<PieceType>,
 }
 
 impl UncheckedPieceMoveEvent {
     pub fn new(selected: SelectedPiece, target: Entity) -> Self {
-        Self { selected, target }
+        Self {
+            selected,
+            target,
+            promotion: None,
+        }
+    }
+
+    pub fn promote(selected: SelectedPiece, target: Entity, promotion: PieceType) -> Self {
+        Self {
+            selected,
+            target,
+            promotion: Some(promotion),
+        }
     }
 }
 
@@ -20,15 +37,23 @@ pub enum MoveTarget {
 pub struct CheckedPieceMoveEvent {
     pub selected: SelectedPiece,
     pub target: MoveTarget,
+    pub promotion: Option<PieceType>,
 }
 
 impl CheckedPieceMoveEvent {
     pub fn new(selected: SelectedPiece, target: MoveTarget) -> Self {
-        Self { selected, target }
+        Self {
+            selected,
+            target,
+            promotion: None,
+        }
     }
 
     pub fn legal(event: &UncheckedPieceMoveEvent) -> Self {
-        Self::new(event.selected.clone(), MoveTarget::Legal(event.target))
+        Self {
+            promotion: event.promotion,
+            ..Self::new(event.selected.clone(), MoveTarget::Legal(event.target))
+        }
     }
 
     pub fn illegal(event: &UncheckedPieceMoveEvent) -> Self {
@@ -42,4 +67,57 @@ pub enum PieceSelectionEvent {
     Reselect(Entity),
 }
 
-pub struct PlayedMoveEvent(pub PlayedMove);
+/// Fired to play a move that has not been made by moving a piece on the board,
+/// like a move entered as text or the move of the engine
+pub struct PlayMoveEvent(pub Move);
+
+pub struct PlayedMoveEvent {
+    pub played_move: PlayedMove,
+    /// The move in Standard Algebraic Notation
+    pub san: String,
+    /// The zobrist key of the position after the move
+    pub key: u64,
+}
+
+/// Fired once when the game has ended
+pub struct GameOverEvent(pub GameResult);
+
+/// Fired when the player to move claims a draw
+pub struct ClaimDrawEvent;
+
+/// Fired to replace the current game with one that starts from the given position
+/// and consists of the given legal moves
+pub struct LoadGameEvent {
+    pub start: Position,
+    pub moves: Vec<Move>,
+}
+
+impl LoadGameEvent {
+    /// Returns an event to start a new game from the given position
+    pub fn new(start: Position) -> Self {
+        Self {
+            start,
+            moves: Vec::new(),
+        }
+    }
+}
+
+/// Fired to let the engine search a move for the side to move
+pub enum EngineEvent {
+    /// Starts searching the current position, the best move is played once the search has finished
+    Start,
+    /// Stops the running search early and plays the best move found so far
+    Stop,
+}
+
+/// Fired to step through the game held by `Replay`
+pub enum ReplayEvent {
+    /// Starts replaying the given game from its first position
+    Load(Box<PgnGame>),
+    Start,
+    Back,
+    Forward,
+    End,
+    /// Stops replaying and continues the game from the current position
+    Exit,
+}
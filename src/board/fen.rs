@@ -0,0 +1,400 @@
+use crate::board::components::{CastlingSide, Location, Piece, PieceColor, PieceType};
+use crate::board::position::{CastlingRights, Position};
+use std::fmt;
+
+/// The FEN of the initial position
+pub const INITIAL_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Describes why a FEN string could not be parsed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FenError {
+    /// The named field is missing
+    MissingField(&'static str),
+    /// There are more than six fields
+    TrailingField(String),
+    /// The piece placement does not describe exactly eight ranks
+    RankCount(usize),
+    /// The rank with the given number does not describe exactly eight squares
+    RankLength(usize),
+    /// The character is neither a piece letter nor a digit between 1 and 8
+    InvalidPiece(char),
+    /// The given color has not exactly one king
+    KingCount(PieceColor),
+    /// A pawn stands on the first or last rank, which no pawn can reach without being promoted
+    PawnOnBackRank,
+    /// The king of the side that just moved is attacked, so it could be captured
+    OpponentInCheck,
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {} field", field),
+            FenError::TrailingField(field) => write!(f, "unexpected trailing field '{}'", field),
+            FenError::RankCount(count) => {
+                write!(f, "piece placement has {} ranks instead of 8", count)
+            }
+            FenError::RankLength(rank) => {
+                write!(f, "rank {} does not describe exactly 8 squares", rank)
+            }
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{}' in piece placement", c),
+            FenError::KingCount(color) => write!(f, "{} must have exactly one king", color),
+            FenError::PawnOnBackRank => write!(f, "pawns cannot stand on the first or last rank"),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            FenError::InvalidSideToMove(field) => {
+                write!(f, "invalid side to move '{}', expected 'w' or 'b'", field)
+            }
+            FenError::InvalidCastling(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::InvalidEnPassant(field) => {
+                write!(f, "invalid en passant square '{}'", field)
+            }
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "invalid halfmove clock '{}'", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "invalid fullmove number '{}'", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Position {
+    /// Parses a position from Forsyth–Edwards Notation.
+    /// The halfmove clock and fullmove number are optional and default to 0 and 1.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields
+            .next()
+            .ok_or(FenError::MissingField("piece placement"))?;
+        let side_to_move = fields
+            .next()
+            .ok_or(FenError::MissingField("side to move"))?;
+        let castling = fields
+            .next()
+            .ok_or(FenError::MissingField("castling rights"))?;
+        let en_passant = fields
+            .next()
+            .ok_or(FenError::MissingField("en passant square"))?;
+
+        let mut position = Position::empty();
+        parse_placement(&mut position, placement)?;
+        position.side_to_move = match side_to_move {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            _ => return Err(FenError::InvalidSideToMove(side_to_move.to_string())),
+        };
+        position.castling_rights = parse_castling(castling)?;
+        position.en_passant = match en_passant {
+            "-" => None,
+            _ => match Location::from_notation(en_passant) {
+                Some(location) if could_have_been_skipped(&position, &location) => Some(location),
+                _ => return Err(FenError::InvalidEnPassant(en_passant.to_string())),
+            },
+        };
+        if let Some(field) = fields.next() {
+            position.halfmove_clock = field
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(field.to_string()))?;
+        }
+        if let Some(field) = fields.next() {
+            position.fullmove_number = match field.parse() {
+                Ok(number) if number > 0 => number,
+                _ => return Err(FenError::InvalidFullmoveNumber(field.to_string())),
+            };
+        }
+        if let Some(field) = fields.next() {
+            return Err(FenError::TrailingField(field.to_string()));
+        }
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            if position.pieces_of(PieceType::King, &color).count() != 1 {
+                return Err(FenError::KingCount(color));
+            }
+        }
+        let back_ranks = position
+            .pieces()
+            .into_iter()
+            .filter(|(piece, _)| piece.kind == PieceType::Pawn)
+            .any(|(_, location)| location.y == 0 || location.y == 7);
+        if back_ranks {
+            return Err(FenError::PawnOnBackRank);
+        }
+        if position.is_in_check(&position.side_to_move.opponent()) {
+            return Err(FenError::OpponentInCheck);
+        }
+        mark_moved_pieces(&mut position);
+        position.reset_key();
+        Ok(position)
+    }
+
+    /// Serializes this position to Forsyth–Edwards Notation
+    pub fn to_fen(&self) -> String {
+        let mut placement = Vec::with_capacity(8);
+        for y in (0..8).rev() {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..8 {
+                match self.piece_at(&Location::new(x, y)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push(piece_char(&piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            placement.push(rank);
+        }
+
+        let side_to_move = match self.side_to_move {
+            PieceColor::White => 'w',
+            PieceColor::Black => 'b',
+        };
+        let rights = &self.castling_rights;
+        let castling: String = [
+            (rights.white_kingside, 'K'),
+            (rights.white_queenside, 'Q'),
+            (rights.black_kingside, 'k'),
+            (rights.black_queenside, 'q'),
+        ]
+        .into_iter()
+        .filter(|(right, _)| *right)
+        .map(|(_, c)| c)
+        .collect();
+        let en_passant = self
+            .en_passant
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        format!(
+            "{} {} {} {} {} {}",
+            placement.join("/"),
+            side_to_move,
+            if castling.is_empty() { "-" } else { &castling },
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+/// Returns the FEN letter of the given piece, upper case for white and lower case for black
+fn piece_char(piece: &Piece) -> char {
+    match piece.color {
+        PieceColor::White => piece.notation(),
+        PieceColor::Black => piece.notation().to_ascii_lowercase(),
+    }
+}
+
+/// Parses the piece placement field, ranks are listed from the 8th to the 1st
+fn parse_placement(position: &mut Position, placement: &str) -> Result<(), FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::RankCount(ranks.len()));
+    }
+    for (row, rank) in ranks.into_iter().enumerate() {
+        let y = 7 - row;
+        let mut x = 0;
+        for c in rank.chars() {
+            if x >= 8 {
+                return Err(FenError::RankLength(y + 1));
+            }
+            match c {
+                '1'..='8' => x += c as usize - '0' as usize,
+                _ => {
+                    position.set(Location::new(x, y), parse_piece(c)?);
+                    x += 1;
+                }
+            }
+        }
+        if x != 8 {
+            return Err(FenError::RankLength(y + 1));
+        }
+    }
+    Ok(())
+}
+
+/// Parses a piece letter, upper case letters are white and lower case letters black pieces
+fn parse_piece(c: char) -> Result<Piece, FenError> {
+    let kind = match c.to_ascii_uppercase() {
+        'K' => PieceType::King,
+        'Q' => PieceType::Queen,
+        'R' => PieceType::Rook,
+        'B' => PieceType::Bishop,
+        'N' => PieceType::Knight,
+        'P' => PieceType::Pawn,
+        _ => return Err(FenError::InvalidPiece(c)),
+    };
+    let color = match c.is_ascii_uppercase() {
+        true => PieceColor::White,
+        false => PieceColor::Black,
+    };
+    Ok(Piece::new(kind, color))
+}
+
+fn parse_castling(field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+    for c in field.chars() {
+        let right = match c {
+            'K' => &mut rights.white_kingside,
+            'Q' => &mut rights.white_queenside,
+            'k' => &mut rights.black_kingside,
+            'q' => &mut rights.black_queenside,
+            _ => return Err(FenError::InvalidCastling(field.to_string())),
+        };
+        if *right {
+            return Err(FenError::InvalidCastling(field.to_string()));
+        }
+        *right = true;
+    }
+    Ok(rights)
+}
+
+/// Returns true if a pawn of the side that just moved can have skipped the given square
+/// with a two square push, so the pawn stands in front of it and both the skipped square
+/// and the initial square of the pawn are empty
+fn could_have_been_skipped(position: &Position, location: &Location) -> bool {
+    let color = position.side_to_move.opponent();
+    let direction = color.pawn_direction();
+    if location.y as isize != color.pawn_rank() as isize + direction {
+        return false;
+    }
+    let is_empty = |square: Option<Location>| {
+        square.is_some_and(|square| position.piece_at(&square).is_none())
+    };
+    let pawn = location
+        .translate(0, direction)
+        .and_then(|square| position.piece_at(&square));
+    pawn.is_some_and(|pawn| pawn.kind == PieceType::Pawn && pawn.color == color)
+        && is_empty(Some(*location))
+        && is_empty(location.translate(0, -direction))
+}
+
+/// A FEN does not tell which pieces have moved, so pawns off their initial rank
+/// and kings and rooks without castling rights are considered moved
+fn mark_moved_pieces(position: &mut Position) {
+    for (mut piece, location) in position.pieces() {
+        let rank = piece.color.home_rank();
+        let rights = &position.castling_rights;
+        piece.has_moved = match piece.kind {
+            PieceType::Pawn => location.y != piece.color.pawn_rank(),
+            PieceType::King => {
+                location != Location::new(4, rank)
+                    || !(rights.can_castle(&piece.color, &CastlingSide::Kingside)
+                        || rights.can_castle(&piece.color, &CastlingSide::Queenside))
+            }
+            PieceType::Rook => ![CastlingSide::Kingside, CastlingSide::Queenside]
+                .iter()
+                .any(|side| {
+                    location == Location::new(side.rook_source_file(), rank)
+                        && rights.can_castle(&piece.color, side)
+                }),
+            _ => false,
+        };
+        position.set(location, piece);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::position::Move;
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            INITIAL_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 47",
+        ] {
+            assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn played_moves_are_serialized() {
+        let mut position = Position::default();
+        position.make_move(Move::new(Location::new(4, 1), Location::new(4, 3), None));
+        assert_eq!(
+            position.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+    }
+
+    #[test]
+    fn malformed_fields_are_reported() {
+        let cases = [
+            ("", FenError::MissingField("piece placement")),
+            ("8/8/8/8/8/8/8/8", FenError::MissingField("side to move")),
+            ("8/8/8 w - -", FenError::RankCount(3)),
+            ("k7/8/8/8/8/8/8/K6 w - -", FenError::RankLength(1)),
+            ("k8/8/8/8/8/8/8/K7 w - -", FenError::RankLength(8)),
+            ("k7/8/8/8/8/8/8/X7 w - -", FenError::InvalidPiece('X')),
+            (
+                "8/8/8/8/8/8/8/K7 w - -",
+                FenError::KingCount(PieceColor::Black),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 x - -",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w KK -",
+                FenError::InvalidCastling("KK".to_string()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - e4",
+                FenError::InvalidEnPassant("e4".to_string()),
+            ),
+            (
+                "4k3/8/8/3NP3/8/8/8/4K3 w - d6 0 1",
+                FenError::InvalidEnPassant("d6".to_string()),
+            ),
+            (
+                "4k3/8/3n4/3pP3/8/8/8/4K3 w - d6 0 1",
+                FenError::InvalidEnPassant("d6".to_string()),
+            ),
+            (
+                "4k3/3n4/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                FenError::InvalidEnPassant("d6".to_string()),
+            ),
+            (
+                "4k3/8/8/8/3p4/8/8/4K3 b - d3 0 1",
+                FenError::InvalidEnPassant("d3".to_string()),
+            ),
+            ("7k/8/8/8/8/8/8/K6R w - - 0 1", FenError::OpponentInCheck),
+            ("7k/8/8/8/8/8/8/K5P1 w - - 0 1", FenError::PawnOnBackRank),
+            ("3p3k/8/8/8/8/8/8/K7 b - - 0 1", FenError::PawnOnBackRank),
+            (
+                "k7/8/8/8/8/8/8/K7 w - - x",
+                FenError::InvalidHalfmoveClock("x".to_string()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+            (
+                "k7/8/8/8/8/8/8/K7 w - - 0 1 x",
+                FenError::TrailingField("x".to_string()),
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(Position::from_fen(fen), Err(error), "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::board::components::PieceColor;
+use crate::board::position::{Move, Position};
+
+/// A move of a game together with its annotations and the lines played instead of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameNode {
+    pub mv: Move,
+    /// The move in Standard Algebraic Notation
+    pub san: String,
+    /// Numeric Annotation Glyphs like `$1`, which is the same as the `!` suffix
+    pub nags: Vec<u8>,
+    /// The comments following the move
+    pub comments: Vec<String>,
+    /// Alternative lines that start with a different move from the position before this move
+    pub variations: Vec<Line>,
+}
+
+impl GameNode {
+    /// Creates a node without annotations for the given move, which has to be legal in `position`
+    pub fn new(position: &Position, mv: Move) -> Self {
+        Self {
+            mv,
+            san: position.san(&mv),
+            nags: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A sequence of moves, either the mainline of a game or a variation
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// The comments in front of the first move
+    pub comments: Vec<String>,
+    /// The NAGs in front of the first move
+    pub nags: Vec<u8>,
+    pub nodes: Vec<GameNode>,
+}
+
+/// A game with all its variations and annotations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameTree {
+    pub start: Position,
+    pub mainline: Line,
+}
+
+impl GameTree {
+    /// Creates a game without annotations that consists of the given legal moves
+    pub fn from_moves(start: Position, moves: &[Move]) -> Self {
+        let mut position = start.clone();
+        let nodes = moves
+            .iter()
+            .map(|mv| {
+                let node = GameNode::new(&position, *mv);
+                position.make_move(*mv);
+                node
+            })
+            .collect();
+        Self {
+            start,
+            mainline: Line {
+                nodes,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Returns the moves of the mainline
+    pub fn mainline_moves(&self) -> Vec<Move> {
+        self.mainline.nodes.iter().map(|node| node.mv).collect()
+    }
+
+    /// Returns the move number and the moving color of the move at the given ply,
+    /// which counts the half moves from the start position
+    pub fn move_number(&self, ply: usize) -> (usize, PieceColor) {
+        // count from the white move in front of the start position if black moves first
+        let offset = match self.start.side_to_move {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+        };
+        let color = match (ply + offset) % 2 {
+            0 => PieceColor::White,
+            _ => PieceColor::Black,
+        };
+        (self.start.fullmove_number + (ply + offset) / 2, color)
+    }
+}
+
+/// Returns the suffix annotation like `!` or `?!` for the given NAG, if it has one
+pub fn nag_symbol(nag: u8) -> Option<&'static str> {
+    match nag {
+        1 => Some("!"),
+        2 => Some("?"),
+        3 => Some("!!"),
+        4 => Some("??"),
+        5 => Some("!?"),
+        6 => Some("?!"),
+        _ => None,
+    }
+}
+
+/// Returns the NAG for the given suffix annotation like `!` or `?!`
+pub fn nag_of_symbol(symbol: &str) -> Option<u8> {
+    (1..=6).find(|nag| nag_symbol(*nag) == Some(symbol))
+}
@@ -1,14 +1,38 @@
-use crate::board::components::{PieceColor, PieceType};
-use crate::board::events::CheckedPieceMoveEvent;
+use crate::board::components::{CastlingSide, PieceColor, PieceType};
+use crate::board::pgn::PgnGame;
+use crate::board::position::{IrreversibleState, Move, Position};
+use crate::board::search::{SearchLimits, SearchResult};
+use crate::constants::{ENGINE_SEARCH_SECS, REPLAY_MOVE_SECS};
 use crate::{Location, Piece};
 use bevy::prelude::*;
 use itertools::Itertools;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+pub mod attacks;
+pub mod bitboard;
 pub mod components;
+pub mod diagram;
+pub mod epd;
+pub mod evaluation;
 pub mod events;
+pub mod fen;
+pub mod game_tree;
+pub mod movegen;
+pub mod perft;
+pub mod pgn;
+pub mod pgn_reader;
 pub mod plugin;
+pub mod position;
+pub mod rules;
+pub mod san;
+pub mod search;
 mod systems;
+pub mod uci;
 mod utils;
+pub mod zobrist;
 
 /// Holds the currently selected piece square where it sits on and all legal moves it can take
 #[derive(Clone)]
@@ -21,23 +45,16 @@ pub struct SelectedPiece {
 }
 
 impl SelectedPiece {
-    pub fn new(
-        square: Entity,
-        piece: Entity,
-        world: &World,
-        pieces: &Query<(&Piece, &Location)>,
-    ) -> Option<Self> {
+    pub fn new(square: Entity, piece: Entity, world: &World, position: &Position) -> Option<Self> {
         let piece_comp = *world.get_entity(piece)?.get::<Piece>()?;
         let location_comp = *world.get_entity(square)?.get::<Location>()?;
 
-        let (same_color_pieces, different_color_pieces) =
-            utils::grouped_piece_locations(&piece_comp.color, pieces);
-        let possible_targets = SelectedPiece::legal_moves(
-            &piece_comp,
-            &location_comp,
-            &same_color_pieces,
-            &different_color_pieces,
-        );
+        let possible_targets = position
+            .legal_moves_from(&location_comp)
+            .into_iter()
+            .map(|mv| mv.to)
+            .unique()
+            .collect();
         Some(Self {
             square,
             piece,
@@ -47,186 +64,335 @@ impl SelectedPiece {
         })
     }
 
-    /// Returns all possible squares the given `Piece` can move from the given `Location`.
-    /// It is not checked whether the returned locations make up a legal move.
-    fn legal_moves(
-        piece: &Piece,
-        location: &Location,
-        same_color_pieces: &[Location],
-        different_color_pieces: &[Location],
-    ) -> Vec<Location> {
-        match piece.kind {
-            PieceType::King => SelectedPiece::king_moves(location),
-            PieceType::Queen => SelectedPiece::rook_moves(location, same_color_pieces)
-                .into_iter()
-                .chain(SelectedPiece::bishop_moves(location, same_color_pieces).into_iter())
-                .collect(),
-            PieceType::Rook => SelectedPiece::rook_moves(location, same_color_pieces),
-            PieceType::Bishop => SelectedPiece::bishop_moves(location, same_color_pieces),
-            PieceType::Knight => SelectedPiece::knight_moves(location),
-            PieceType::Pawn => SelectedPiece::pawn_moves(location, piece, different_color_pieces),
-        }
-        .into_iter()
-        .filter(|loc| !same_color_pieces.contains(loc))
-        .collect()
-    }
-
-    /// Returns all possible king moves
-    /// TODO handle castling
-    fn king_moves(location: &Location) -> Vec<Location> {
-        let mut offsets = vec![-1, 0, 1].into_iter().permutations(2).collect_vec();
-        offsets.extend(vec![vec![-1, -1], vec![1, 1]]);
-        utils::translate_from_offsets(location, offsets)
-    }
-
-    /// Returns all possible knight moves
-    fn knight_moves(location: &Location) -> Vec<Location> {
-        let offsets = vec![
-            vec![-2, -1],
-            vec![-2, 1],
-            vec![-1, -2],
-            vec![-1, 2],
-            vec![1, -2],
-            vec![1, 2],
-            vec![2, -1],
-            vec![2, 1],
-        ];
-        utils::translate_from_offsets(location, offsets)
-    }
-
-    /// Returns all possible pawn moves
-    /// TODO: handle piece promotion and en passant
-    fn pawn_moves(
-        location: &Location,
-        piece: &Piece,
-        different_color_pieces: &[Location],
-    ) -> Vec<Location> {
-        let y_modifier = match piece.color {
-            PieceColor::White => 1,
-            PieceColor::Black => -1,
-        };
-        let mut squares = Vec::new();
-        match location.translate(0, y_modifier) {
-            Some(new_loc) => {
-                // a pawn cannot capture in the same way it moves
-                if !different_color_pieces.contains(&new_loc) {
-                    squares.push(new_loc);
-                }
-                // a pawn can move two squares on its first move
-                if !piece.has_moved {
-                    let first_move_square = location.translate(0, 2 * y_modifier).unwrap();
-                    if !different_color_pieces.contains(&first_move_square) {
-                        squares.push(first_move_square);
-                    }
-                }
-                // add diagonally forward squares for capturing
-                squares.extend(
-                    vec![1, -1]
-                        .into_iter()
-                        .filter_map(|x_offset| location.translate(x_offset, y_modifier))
-                        .filter(|loc| different_color_pieces.contains(loc)),
-                );
-            }
-            None => {
-                // TODO: handle pawn promotion
-                println!("TODO: pawn promotion has not been implemented");
-            }
-        }
-        squares
-    }
-
-    /// Returns all possible Rook moves
-    fn rook_moves(location: &Location, same_color_pieces: &[Location]) -> Vec<Location> {
-        let x_ranges: Vec<Vec<usize>> = vec![
-            (0..location.x).rev().collect(),
-            (location.x + 1..8).collect(),
-        ];
-        let y_ranges: Vec<Vec<usize>> = vec![
-            (0..location.y).rev().collect(),
-            (location.y + 1..8).collect(),
-        ];
-
-        let x_locations = x_ranges.into_iter().flat_map(|x_range| {
-            x_range
-                .into_iter()
-                .map(|x| Location::new(x, location.y))
-                .take_while(|loc| !same_color_pieces.contains(loc))
-        });
+    /// Returns true if moving to the given location promotes the selected pawn
+    pub fn is_promotion(&self, target: &Location) -> bool {
+        self.piece_comp.kind == PieceType::Pawn
+            && target.y == self.piece_comp.color.opponent().home_rank()
+    }
+}
 
-        let y_locations = y_ranges.into_iter().flat_map(|y_range| {
-            y_range
-                .into_iter()
-                .map(|y| Location::new(location.x, y))
-                .take_while(|loc| !same_color_pieces.contains(loc))
-        });
-        x_locations.chain(y_locations).collect()
+/// Describes a move that has been played and everything it changed on the board
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PlayedMove {
+    /// The moved piece as it was before the move
+    pub piece: Piece,
+    pub source: Location,
+    pub target: Location,
+    /// The opponent piece that has been taken with this move, if any
+    pub captured: Option<Piece>,
+    /// The castling side if this move castles the king
+    pub castling: Option<CastlingSide>,
+    /// Whether this move captured a pawn en passant
+    pub en_passant: bool,
+    /// The piece type a pawn has been promoted to with this move
+    pub promotion: Option<PieceType>,
+    /// The state before this move that is needed to take it back
+    pub previous: IrreversibleState,
+}
+
+impl PlayedMove {
+    /// Returns the location of the captured piece, which differs from the target when capturing en passant
+    pub fn captured_location(&self) -> Option<Location> {
+        self.captured.map(|_| match self.en_passant {
+            true => Location::new(self.target.x, self.source.y),
+            false => self.target,
+        })
     }
+}
 
-    /// Returns all possible bishop moves
-    fn bishop_moves(location: &Location, same_color_pieces: &[Location]) -> Vec<Location> {
-        let offset_modifiers = vec![(1, 1), (1, -1), (-1, 1), (-1, -1)];
-        offset_modifiers
-            .into_iter()
-            .flat_map(|(x_mod, y_mod)| {
-                (1..7)
-                    .map_while(move |offset| location.translate(offset * x_mod, offset * y_mod))
-                    .take_while(|loc| !same_color_pieces.contains(loc))
+/// Holds all moves of the current game together with their Standard Algebraic Notation
+#[derive(Default)]
+pub struct PlayedMoves {
+    /// The position the game started from
+    start: Position,
+    played: Vec<PlayedMove>,
+    /// The SAN of each played move, which depends on the position before the move
+    san: Vec<String>,
+}
+
+impl PlayedMoves {
+    pub fn push(&mut self, played_move: PlayedMove, san: String) {
+        self.played.push(played_move);
+        self.san.push(san);
+    }
+
+    /// Removes all moves and starts a new game from the given position
+    pub fn reset(&mut self, start: Position) {
+        self.start = start;
+        self.played.clear();
+        self.san.clear();
+    }
+
+    /// Returns the position the game started from
+    pub fn start(&self) -> &Position {
+        &self.start
+    }
+
+    /// Returns every played move together with its SAN
+    pub fn iter(&self) -> impl Iterator<Item = (&PlayedMove, &str)> {
+        self.played.iter().zip(self.san.iter().map(String::as_str))
+    }
+
+    /// Returns the played moves as they have been passed to `Position::make_move`
+    pub fn moves(&self) -> Vec<Move> {
+        self.played
+            .iter()
+            .map(|played_move| {
+                Move::new(
+                    played_move.source,
+                    played_move.target,
+                    played_move.promotion,
+                )
             })
             .collect()
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct PlayedMove {
-    pub piece: Entity,
-    pub piece_comp: Piece,
-    pub source_square: Entity,
-    pub source_square_comp: Location,
-    pub target_square: Entity,
-    pub target_square_comp: Location,
+/// Holds an imported game while the player steps through it, the board does not accept moves meanwhile
+pub struct Replay {
+    /// The imported game with all its variations and annotations
+    pub game: PgnGame,
+    /// The moves of the mainline that is stepped through
+    pub moves: Vec<Move>,
+    /// The number of moves that are currently played on the board
+    pub current: usize,
+    /// Whether the next move is played automatically whenever the timer finishes
+    pub autoplay: bool,
+    pub timer: Timer,
 }
 
-impl PlayedMove {
-    pub fn from_event(
-        event: &CheckedPieceMoveEvent,
-        target_entity: Entity,
-        target_location: Location,
-    ) -> Self {
-        PlayedMove {
-            piece: event.selected.piece,
-            piece_comp: event.selected.piece_comp,
-            source_square: event.selected.square,
-            source_square_comp: event.selected.location_comp,
-            target_square: target_entity,
-            target_square_comp: target_location,
+impl Replay {
+    pub fn new(game: PgnGame) -> Self {
+        Self {
+            moves: game.tree.mainline_moves(),
+            game,
+            current: 0,
+            autoplay: false,
+            timer: Timer::from_seconds(REPLAY_MOVE_SECS, true),
         }
     }
 
-    pub fn notation(&self) -> String {
-        if self.piece_comp.kind == PieceType::Pawn {
-            format!("{}", self.target_square_comp)
-        } else {
-            format!("{}{}", self.piece_comp.notation(), self.target_square_comp)
+    /// Returns true if all moves of the game are played on the board
+    pub fn is_at_end(&self) -> bool {
+        self.current == self.moves.len()
+    }
+}
+
+/// A search of the engine for the best move in the current position, which runs on its own thread.
+/// The board does not accept moves meanwhile.
+pub struct EngineSearch {
+    /// The zobrist key of the searched position, the result is discarded if the position has changed
+    pub key: u64,
+    /// Ends the search early with the result of its last complete iteration
+    pub stop: Arc<AtomicBool>,
+    result: Arc<Mutex<Option<SearchResult>>>,
+}
+
+impl EngineSearch {
+    /// Starts searching the position, `history` holds the zobrist keys of the game to avoid repetitions
+    pub fn start(position: &Position, history: &[u64]) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let result = Arc::new(Mutex::new(None));
+        let (thread_stop, thread_result) = (stop.clone(), result.clone());
+        let (thread_position, history) = (position.clone(), history.to_vec());
+        std::thread::spawn(move || {
+            let limits = SearchLimits::time(Duration::from_secs_f32(ENGINE_SEARCH_SECS));
+            let result = search::search(
+                &thread_position,
+                &history,
+                &limits,
+                &thread_stop,
+                |result| println!("INFO: {}", result),
+            );
+            *thread_result.lock().unwrap() = Some(result);
+        });
+        Self {
+            key: position.zobrist_key(),
+            stop,
+            result,
         }
     }
+
+    /// Returns the result once the search has finished
+    pub fn take_result(&self) -> Option<SearchResult> {
+        self.result.lock().ok()?.take()
+    }
 }
 
+/// Holds a pawn move to the last rank while the player chooses the promotion piece
+#[derive(Clone)]
+pub struct PendingPromotion {
+    pub selected: SelectedPiece,
+    pub target: Entity,
+}
+
+/// Holds all pieces that have been taken, grouped by the color of the capturing side
 #[derive(Default)]
-pub struct PlayedMoves(pub Vec<PlayedMove>);
+pub struct CapturedPieces {
+    pub white: Vec<Piece>,
+    pub black: Vec<Piece>,
+}
 
-pub struct CurrentPlayer(pub PieceColor);
+impl CapturedPieces {
+    /// Records the given `Piece` as taken by `color`
+    pub fn push(&mut self, color: PieceColor, piece: Piece) {
+        match color {
+            PieceColor::White => self.white.push(piece),
+            PieceColor::Black => self.black.push(piece),
+        }
+    }
+
+    /// Returns all pieces taken by `color`
+    pub fn taken_by(&self, color: PieceColor) -> &[Piece] {
+        match color {
+            PieceColor::White => &self.white,
+            PieceColor::Black => &self.black,
+        }
+    }
+}
+
+/// The final score of a finished game
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameOutcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameOutcome {
+    /// Returns the outcome where the given color wins
+    pub fn win_for(color: &PieceColor) -> Self {
+        match color {
+            PieceColor::White => GameOutcome::WhiteWins,
+            PieceColor::Black => GameOutcome::BlackWins,
+        }
+    }
+}
 
-impl Default for CurrentPlayer {
-    fn default() -> Self {
-        Self(PieceColor::White)
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self {
+            GameOutcome::WhiteWins => "1-0",
+            GameOutcome::BlackWins => "0-1",
+            GameOutcome::Draw => "½-½",
+        };
+        write!(f, "{}", outcome)
     }
 }
 
-impl CurrentPlayer {
-    pub fn switch(&mut self) {
-        match self.0 {
-            PieceColor::Black => self.0 = PieceColor::White,
-            PieceColor::White => self.0 = PieceColor::Black,
+/// The rule that ended the game
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameOverReason {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+}
+
+impl fmt::Display for GameOverReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            GameOverReason::Checkmate => "Checkmate",
+            GameOverReason::Stalemate => "Stalemate",
+            GameOverReason::InsufficientMaterial => "Insufficient material",
+            GameOverReason::FiftyMoveRule => "Fifty-move rule",
+            GameOverReason::SeventyFiveMoveRule => "Seventy-five-move rule",
+            GameOverReason::ThreefoldRepetition => "Threefold repetition",
+            GameOverReason::FivefoldRepetition => "Fivefold repetition",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Holds the result once the game is over, no further moves are accepted while it exists
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct GameResult {
+    pub outcome: GameOutcome,
+    pub reason: GameOverReason,
+}
+
+impl GameResult {
+    /// Checks whether the side to move has no legal moves left and returns the result,
+    /// which is checkmate if its king is attacked and stalemate otherwise
+    pub fn detect(position: &Position) -> Option<Self> {
+        if !position.legal_moves().is_empty() {
+            return None;
         }
+
+        let color = position.side_to_move;
+        let result = match position.is_in_check(&color) {
+            true => GameResult {
+                outcome: GameOutcome::win_for(&color.opponent()),
+                reason: GameOverReason::Checkmate,
+            },
+            false => GameResult::draw(GameOverReason::Stalemate),
+        };
+        Some(result)
+    }
+
+    /// Returns a drawn result for the given reason
+    pub fn draw(reason: GameOverReason) -> Self {
+        GameResult {
+            outcome: GameOutcome::Draw,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.outcome, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+
+    fn detect(fen: &str) -> Option<GameResult> {
+        GameResult::detect(&Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn detects_checkmate() {
+        assert_eq!(
+            detect("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            Some(GameResult {
+                outcome: GameOutcome::BlackWins,
+                reason: GameOverReason::Checkmate,
+            })
+        );
+        assert_eq!(
+            detect("6k1/5ppp/8/8/8/8/8/3R2K1 w - - 0 1"),
+            None,
+            "the mate is not played yet"
+        );
+        assert_eq!(
+            detect("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 1 1"),
+            Some(GameResult {
+                outcome: GameOutcome::WhiteWins,
+                reason: GameOverReason::Checkmate,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_stalemate() {
+        assert_eq!(
+            detect("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(GameResult::draw(GameOverReason::Stalemate))
+        );
+        // the same position with black in check is mate
+        assert_eq!(
+            detect("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").map(|result| result.reason),
+            Some(GameOverReason::Checkmate)
+        );
+        // a king without moves is no stalemate while another piece can move
+        assert_eq!(detect("7k/5Q2/6K1/8/8/8/p7/8 b - - 0 1"), None);
+        assert_eq!(detect(INITIAL_FEN), None);
     }
 }
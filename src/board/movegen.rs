@@ -0,0 +1,392 @@
+use crate::board::attacks;
+use crate::board::bitboard::Bitboard;
+use crate::board::components::{CastlingSide, Location, PieceColor, PieceType};
+use crate::board::position::{Move, Position};
+
+/// Returns all legal moves for the side to move.
+/// Moves that would leave the own king in check are not included, this also covers
+/// pinned pieces and moves that do not answer a check.
+pub fn legal_moves(position: &Position) -> Vec<Move> {
+    let mut moves = pseudo_legal_moves(position);
+    let mut position = position.clone();
+    moves.retain(|mv| is_king_safe_after(&mut position, mv));
+    moves
+}
+
+/// Returns true if the given location is attacked by any piece of the given color
+pub fn is_attacked(position: &Position, location: &Location, by: &PieceColor) -> bool {
+    let occupied = position.occupied();
+    let queens = position.pieces_of(PieceType::Queen, by);
+    let attackers = (attacks::pawn_attacks(&by.opponent(), location)
+        & position.pieces_of(PieceType::Pawn, by))
+        | (attacks::knight_attacks(location) & position.pieces_of(PieceType::Knight, by))
+        | (attacks::king_attacks(location) & position.pieces_of(PieceType::King, by))
+        | (attacks::bishop_attacks(location, occupied)
+            & (position.pieces_of(PieceType::Bishop, by) | queens))
+        | (attacks::rook_attacks(location, occupied)
+            & (position.pieces_of(PieceType::Rook, by) | queens));
+    !attackers.is_empty()
+}
+
+/// Plays the given move and returns true if the own king is not attacked afterwards,
+/// the move is taken back before returning
+fn is_king_safe_after(position: &mut Position, mv: &Move) -> bool {
+    let color = position.side_to_move;
+    let played = position.make_move(*mv);
+    let safe = match position.king_location(&color) {
+        Some(king) => !is_attacked(position, &king, &color.opponent()),
+        None => true,
+    };
+    position.unmake_move(&played);
+    safe
+}
+
+/// Returns the squares a non-pawn piece of the given type on the given location attacks
+fn piece_attacks(kind: PieceType, location: &Location, occupied: Bitboard) -> Bitboard {
+    match kind {
+        PieceType::King => attacks::king_attacks(location),
+        PieceType::Queen => attacks::queen_attacks(location, occupied),
+        PieceType::Rook => attacks::rook_attacks(location, occupied),
+        PieceType::Bishop => attacks::bishop_attacks(location, occupied),
+        PieceType::Knight => attacks::knight_attacks(location),
+        PieceType::Pawn => unreachable!("pawn attacks depend on the color"),
+    }
+}
+
+/// Returns all moves for the side to move without checking whether they leave the own king in check
+fn pseudo_legal_moves(position: &Position) -> Vec<Move> {
+    let color = position.side_to_move;
+    let own = position.occupied_by(&color);
+    let occupied = position.occupied();
+    let mut moves = Vec::with_capacity(64);
+
+    for kind in PieceType::ALL {
+        if kind == PieceType::Pawn {
+            continue;
+        }
+        for from in position.pieces_of(kind, &color).squares() {
+            let targets = piece_attacks(kind, &from, occupied) & !own;
+            moves.extend(targets.squares().map(|to| Move::new(from, to, None)));
+        }
+    }
+    pawn_moves(position, &mut moves);
+    castling_moves(position, &mut moves);
+    moves
+}
+
+/// Appends all pawn pushes, captures and en passant captures for the side to move,
+/// a pawn reaching the last rank is expanded into one move per promotion piece
+fn pawn_moves(position: &Position, moves: &mut Vec<Move>) {
+    let color = position.side_to_move;
+    let direction = color.pawn_direction();
+    let empty = !position.occupied();
+    let mut capturable = position.occupied_by(&color.opponent());
+    if let Some(en_passant) = position.en_passant {
+        capturable |= Bitboard::from_location(&en_passant);
+    }
+
+    for from in position.pieces_of(PieceType::Pawn, &color).squares() {
+        let mut targets = attacks::pawn_attacks(&color, &from) & capturable;
+        if let Some(single) = from.translate(0, direction).filter(|to| empty.contains(to)) {
+            targets |= Bitboard::from_location(&single);
+            // a pawn can move two squares from its initial rank if both squares are empty
+            if from.y == color.pawn_rank() {
+                if let Some(double) = single
+                    .translate(0, direction)
+                    .filter(|to| empty.contains(to))
+                {
+                    targets |= Bitboard::from_location(&double);
+                }
+            }
+        }
+
+        for to in targets.squares() {
+            match to.y == color.opponent().home_rank() {
+                true => {
+                    moves.extend(PieceType::PROMOTIONS.map(|kind| Move::new(from, to, Some(kind))))
+                }
+                false => moves.push(Move::new(from, to, None)),
+            }
+        }
+    }
+}
+
+/// Appends the king moves for all castling moves that are currently possible.
+/// Castling requires the right to castle, empty squares between king and rook and
+/// that the king is neither in check nor passes through or lands on an attacked square.
+fn castling_moves(position: &Position, moves: &mut Vec<Move>) {
+    let color = position.side_to_move;
+    let rank = color.home_rank();
+    let king = Location::new(4, rank);
+    if !position.pieces_of(PieceType::King, &color).contains(&king) {
+        return;
+    }
+
+    let occupied = position.occupied();
+    let rooks = position.pieces_of(PieceType::Rook, &color);
+    for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+        if !position.castling_rights.can_castle(&color, &side)
+            || !rooks.contains(&Location::new(side.rook_source_file(), rank))
+            || side
+                .empty_files()
+                .any(|x| occupied.contains(&Location::new(x, rank)))
+            || side
+                .king_path_files()
+                .any(|x| is_attacked(position, &Location::new(x, rank), &color.opponent()))
+        {
+            continue;
+        }
+        moves.push(Move::new(
+            king,
+            Location::new(side.king_target_file(), rank),
+            None,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the legal moves of the position in UCI notation, sorted
+    fn moves_of(fen: &str) -> Vec<String> {
+        let position = Position::from_fen(fen).unwrap();
+        let mut moves: Vec<String> = legal_moves(&position)
+            .into_iter()
+            .map(|mv| mv.to_uci())
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    /// Plays the given UCI moves from the position
+    fn play(fen: &str, moves: &[&str]) -> Position {
+        let mut position = Position::from_fen(fen).unwrap();
+        for uci in moves {
+            let mv = position.parse_uci(uci).unwrap();
+            position.make_move(mv);
+        }
+        position
+    }
+
+    fn has_move(fen: &str, uci: &str) -> bool {
+        moves_of(fen).iter().any(|mv| mv == uci)
+    }
+
+    #[test]
+    fn castles_to_both_sides() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert!(has_move(fen, "e1g1"));
+        assert!(has_move(fen, "e1c1"));
+
+        let position = play(fen, &["e1g1"]);
+        assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        let position = play(fen, &["e1g1", "e8c8"]);
+        assert_eq!(position.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    }
+
+    #[test]
+    fn loses_castling_rights() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let rights = |moves: &[&str]| play(fen, moves).castling_rights;
+
+        let after_rook = rights(&["h1h2"]);
+        assert!(!after_rook.white_kingside);
+        assert!(after_rook.white_queenside);
+
+        let after_king = rights(&["e1e2", "e8d8"]);
+        assert!(!after_king.white_kingside && !after_king.white_queenside);
+        assert!(!after_king.black_kingside && !after_king.black_queenside);
+
+        // capturing a rook on its initial square takes the right of both sides
+        let after_capture = rights(&["a1a8"]);
+        assert!(!after_capture.white_queenside && !after_capture.black_queenside);
+        assert!(after_capture.white_kingside && after_capture.black_kingside);
+
+        // moving the rook back does not bring the right back
+        let position = play(fen, &["h1h2", "a8a7", "h2h1", "a7a8"]);
+        assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w Qk - 4 3");
+        assert!(!has_move(&position.to_fen(), "e1g1"));
+        assert!(has_move(&position.to_fen(), "e1c1"));
+    }
+
+    #[test]
+    fn does_not_castle_through_check() {
+        // the king would pass f1
+        let fen = "4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1";
+        assert!(!has_move(fen, "e1g1"));
+        assert!(has_move(fen, "e1c1"));
+
+        // the king would land on g1
+        assert!(!has_move("4k1r1/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"));
+
+        // the king is in check
+        let fen = "4k3/8/8/8/4r3/8/8/R3K2R w KQ - 0 1";
+        assert!(!has_move(fen, "e1g1"));
+        assert!(!has_move(fen, "e1c1"));
+
+        // only the rook passes the attacked b1
+        assert!(has_move("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1", "e1c1"));
+
+        // a piece between king and rook blocks castling
+        assert!(!has_move("4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1", "e1c1"));
+    }
+
+    #[test]
+    fn captures_en_passant() {
+        let fen = "4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1";
+        let position = play(fen, &["d7d5"]);
+        let after_push = position.to_fen();
+        assert_eq!(after_push, "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2");
+        assert!(has_move(&after_push, "e5d6"));
+
+        let position = play(&after_push, &["e5d6"]);
+        assert_eq!(position.to_fen(), "4k3/8/3P4/8/8/8/8/4K3 b - - 0 2");
+
+        // the right to capture en passant is lost after any other move
+        let position = play(&after_push, &["e1d1", "e8d8"]);
+        assert!(!has_move(&position.to_fen(), "e5d6"));
+    }
+
+    #[test]
+    fn does_not_capture_en_passant_into_check() {
+        // both pawns leave the rank, which opens it for the rook
+        let fen = "8/8/8/KPp4r/8/8/8/7k w - c6 0 1";
+        assert!(!has_move(fen, "b5c6"));
+        assert!(has_move(fen, "b5b6"));
+
+        // the captured pawn shielded the king from the bishop
+        assert!(!has_move("8/8/2b5/3pP3/8/8/6K1/k7 w - d6 0 1", "e5d6"));
+
+        // capturing the pawn that gives check is allowed
+        assert!(has_move("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", "e4d3"));
+    }
+
+    #[test]
+    fn promotes_to_every_piece() {
+        let fen = "r1n1k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let pawn_moves: Vec<String> = moves_of(fen)
+            .into_iter()
+            .filter(|mv| mv.starts_with("b7"))
+            .collect();
+        assert_eq!(
+            pawn_moves,
+            [
+                "b7a8b", "b7a8n", "b7a8q", "b7a8r", "b7b8b", "b7b8n", "b7b8q", "b7b8r", "b7c8b",
+                "b7c8n", "b7c8q", "b7c8r"
+            ]
+        );
+
+        let position = play(fen, &["b7a8n"]);
+        assert_eq!(position.to_fen(), "N1n1k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        let position = play("4k3/8/8/8/8/8/6p1/4K2R b - - 0 1", &["g2h1q"]);
+        assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K2q w - - 0 2");
+    }
+
+    #[test]
+    fn keeps_pinned_pieces_on_the_pin() {
+        // a pinned knight cannot move at all
+        let fen = "4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1";
+        assert!(!moves_of(fen).iter().any(|mv| mv.starts_with("e2")));
+
+        // a pinned rook can still move along the pin and capture the pinning piece
+        let rook_moves: Vec<String> = moves_of("4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1")
+            .into_iter()
+            .filter(|mv| mv.starts_with("e2"))
+            .collect();
+        assert_eq!(rook_moves, ["e2e3", "e2e4", "e2e5", "e2e6", "e2e7", "e2e8"]);
+    }
+
+    #[test]
+    fn only_answers_checks() {
+        // the check can be answered with the king or by blocking with the bishop
+        assert_eq!(
+            moves_of("4k3/4r3/8/8/8/8/8/1B2K2R w K - 0 1"),
+            ["b1e4", "e1d1", "e1d2", "e1f1", "e1f2"]
+        );
+
+        // a double check can only be answered with the king
+        assert_eq!(
+            moves_of("4k3/8/8/8/8/5n2/8/1B2K2r w - - 0 1"),
+            ["e1e2", "e1f2"]
+        );
+    }
+
+    /// Legal moves as listed by the per-square generator that was replaced by the bitboard one
+    const PER_SQUARE_MOVES: [(&str, &str); 13] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "a2a3 a2a4 b1a3 b1c3 b2b3 b2b4 c2c3 c2c4 d2d3 d2d4 e2e3 e2e4 f2f3 f2f4 g1f3 g1h3 g2g3 \
+             g2g4 h2h3 h2h4",
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "a1b1 a1c1 a1d1 a2a3 a2a4 b2b3 c2c4 c3a4 c3b1 c3b5 c3d1 d2c1 d2e3 d2f4 d2g5 d2h6 d5d6 \
+             d5e6 e1c1 e1d1 e1f1 e1g1 e2a6 e2b5 e2c4 e2d1 e2d3 e2f1 e5c4 e5c6 e5d3 e5d7 e5f7 e5g4 \
+             e5g6 f2f4 f3d3 f3e3 f3f4 f3f5 f3f6 f3g3 f3g4 f3h3 f3h5 g2g3 g2g4 g2h3 h1f1 h1g1 h2h4",
+        ),
+        (
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "a5a4 a5a6 b4a4 b4b1 b4b2 b4b3 b4c4 b4d4 b4e4 b4f4 e2e3 e2e4 g2g3 g2g4",
+        ),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "b4c5 c4c5 d2d4 f1f2 f3d4 g1h1",
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "a2a3 a2a4 b1a3 b1c3 b1d2 b2b3 b2b4 c1d2 c1e3 c1f4 c1g5 c1h6 c2c3 c4a6 c4b3 c4b5 c4d3 \
+             c4d5 c4e6 c4f7 d1d2 d1d3 d1d4 d1d5 d1d6 d7c8b d7c8n d7c8q d7c8r e1d2 e1f1 e1f2 e1g1 \
+             e2c3 e2d4 e2f4 e2g1 e2g3 g2g3 g2g4 h1f1 h1g1 h2h3 h2h4",
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "a1a2 a1b1 a1c1 a1d1 a1e1 a3a4 b2b3 b2b4 c3a2 c3a4 c3b1 c3b5 c3d1 c3d5 c4a2 c4a6 c4b3 \
+             c4b5 c4d5 c4e6 c4f7 d3d4 e2d1 e2d2 e2e1 e2e3 f1b1 f1c1 f1d1 f1e1 f3d2 f3d4 f3e1 f3e5 \
+             f3h4 g1h1 g2g3 g5c1 g5d2 g5e3 g5f4 g5f6 g5h4 g5h6 h2h3 h2h4",
+        ),
+        (
+            "4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1",
+            "a1a2 a1a3 a1a4 a1a5 a1a6 a1a7 a1a8 a1b1 a1c1 a1d1 e1c1 e1d1 e1f2 h1f1 h1g1 h1h2 h1h3 \
+             h1h4 h1h5 h1h6 h1h7 h1h8",
+        ),
+        ("8/8/8/KPp4r/8/8/8/7k w - c6 0 1", "a5a4 a5a6 a5b6 b5b6"),
+        (
+            "8/8/2b5/3pP3/8/8/6K1/k7 w - d6 0 1",
+            "e5e6 g2f1 g2f2 g2f3 g2g1 g2g3 g2h1 g2h2 g2h3",
+        ),
+        (
+            "r1n1k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+            "b7a8b b7a8n b7a8q b7a8r b7b8b b7b8n b7b8q b7b8r b7c8b b7c8n b7c8q b7c8r e1d1 e1d2 \
+             e1e2 e1f1 e1f2",
+        ),
+        (
+            "4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1",
+            "e1d1 e1d2 e1f1 e1f2 e2e3 e2e4 e2e5 e2e6 e2e7 e2e8",
+        ),
+        ("4k3/8/8/8/8/5n2/8/1B2K2r w - - 0 1", "e1e2 e1f2"),
+        (
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "a8a1 a8a2 a8a3 a8a4 a8a5 a8a6 a8a7 a8b8 a8c8 a8d8 e8c8 e8d7 e8d8 e8e7 e8f7 e8f8 e8g8 \
+             h8f8 h8g8 h8h1 h8h2 h8h3 h8h4 h8h5 h8h6 h8h7",
+        ),
+    ];
+
+    /// Double pushes the per-square generator listed although the square in between was occupied
+    const PER_SQUARE_PAWN_JUMPS: [(&str, &str); 3] = [
+        (PER_SQUARE_MOVES[1].0, "c2c4"),
+        (PER_SQUARE_MOVES[1].0, "f2f4"),
+        (PER_SQUARE_MOVES[1].0, "h2h4"),
+    ];
+
+    #[test]
+    fn matches_per_square_generator() {
+        for (fen, expected) in PER_SQUARE_MOVES {
+            let expected: Vec<&str> = expected
+                .split_whitespace()
+                .filter(|mv| !PER_SQUARE_PAWN_JUMPS.contains(&(fen, mv)))
+                .collect();
+            assert_eq!(moves_of(fen), expected, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::board::position::{Move, Position};
+
+/// Counts the leaf nodes of the legal move tree of the given depth,
+/// which is compared against known counts to verify the move generator
+pub fn perft(position: &Position, depth: usize) -> u64 {
+    count_nodes(&mut position.clone(), depth)
+}
+
+/// Returns the perft node count below each legal move of the given position,
+/// useful to narrow down which move differs from a reference engine
+pub fn divide(position: &Position, depth: usize) -> Vec<(Move, u64)> {
+    let mut position = position.clone();
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let played = position.make_move(mv);
+            let nodes = count_nodes(&mut position, depth.saturating_sub(1));
+            position.unmake_move(&played);
+            (mv, nodes)
+        })
+        .collect()
+}
+
+/// Walks the move tree with make and unmake, so the position is only copied once
+fn count_nodes(position: &mut Position, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .into_iter()
+        .map(|mv| {
+            let played = position.make_move(mv);
+            let nodes = count_nodes(position, depth - 1);
+            position.unmake_move(&played);
+            nodes
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let position = Position::from_fen(fen).unwrap();
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&position, depth + 1), *nodes, "depth {}", depth + 1);
+        }
+    }
+
+    #[test]
+    fn initial_position() {
+        assert_eq!(
+            Position::from_fen(INITIAL_FEN).unwrap(),
+            Position::default()
+        );
+        assert_perft(INITIAL_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(POSITION_6, &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn divide_sums_up_to_perft() {
+        let position = Position::from_fen(KIWIPETE).unwrap();
+        let moves = divide(&position, 2);
+        assert_eq!(moves.len(), 48);
+        assert_eq!(moves.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+    }
+}
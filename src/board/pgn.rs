@@ -0,0 +1,637 @@
+use crate::board::components::PieceColor;
+use crate::board::fen::{FenError, INITIAL_FEN};
+use crate::board::game_tree::{nag_of_symbol, GameNode, GameTree, Line};
+use crate::board::position::Position;
+use crate::board::san::SanError;
+use crate::board::GameOutcome;
+use std::fmt;
+use std::iter::Peekable;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+/// PGN lines are wrapped before exceeding this length
+const MAX_LINE_LENGTH: usize = 80;
+/// The game termination markers
+pub const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// The Seven Tag Roster of a PGN game, the result tag is derived from the game outcome.
+/// Unknown values are represented with `?` as required by the PGN standard.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PgnHeaders {
+    pub event: String,
+    pub site: String,
+    /// The date in `YYYY.MM.DD` format, unknown parts are replaced with `?`
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnHeaders {
+    fn default() -> Self {
+        Self {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+/// Returns the result token for the given outcome, `*` stands for a game that is still in progress
+pub fn result_token(outcome: Option<GameOutcome>) -> &'static str {
+    match outcome {
+        Some(GameOutcome::WhiteWins) => "1-0",
+        Some(GameOutcome::BlackWins) => "0-1",
+        Some(GameOutcome::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// A game read from or written to PGN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    /// All tag pairs in the order they appear
+    pub tags: Vec<(String, String)>,
+    /// The moves with all variations and annotations,
+    /// the start position is taken from the `FEN` tag if present
+    pub tree: GameTree,
+    /// The result given by the game termination marker, `None` for `*`
+    pub outcome: Option<GameOutcome>,
+}
+
+impl PgnGame {
+    /// Creates a game with the Seven Tag Roster of the given headers, the result tag is derived from the outcome.
+    /// A game that does not start from the initial position gets `SetUp` and `FEN` tags.
+    pub fn new(headers: &PgnHeaders, tree: GameTree, outcome: Option<GameOutcome>) -> Self {
+        let mut tags: Vec<(String, String)> = [
+            ("Event", headers.event.as_str()),
+            ("Site", &headers.site),
+            ("Date", &headers.date),
+            ("Round", &headers.round),
+            ("White", &headers.white),
+            ("Black", &headers.black),
+            ("Result", result_token(outcome)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let fen = tree.start.to_fen();
+        if fen != INITIAL_FEN {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), fen));
+        }
+        Self {
+            tags,
+            tree,
+            outcome,
+        }
+    }
+
+    /// Returns the value of the tag with the given name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Writes the game as PGN with the tags in their given order,
+/// the movetext keeps all variations, comments and NAGs of the game tree
+pub fn write_pgn(game: &PgnGame) -> String {
+    let mut pgn = String::new();
+    for (name, value) in &game.tags {
+        pgn.push_str(&format_tag(name, value));
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    line_tokens(&game.tree, &game.tree.mainline, 0, &mut tokens);
+    tokens.push(result_token(game.outcome).to_string());
+    pgn.push_str(&wrap(&tokens));
+    pgn.push('\n');
+    pgn
+}
+
+/// Formats a tag pair, quotes and backslashes in the value are escaped
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+/// Appends the move numbers, moves and annotations of a line whose first move is played at `first_ply`.
+/// A black move gets a `N...` move number at the start of a line and after comments and variations.
+fn line_tokens(tree: &GameTree, line: &Line, first_ply: usize, tokens: &mut Vec<String>) {
+    tokens.extend(line.nags.iter().map(|nag| format!("${}", nag)));
+    comment_tokens(&line.comments, tokens);
+    let mut interrupted = true;
+    for (i, node) in line.nodes.iter().enumerate() {
+        let (number, color) = tree.move_number(first_ply + i);
+        match color {
+            PieceColor::White => tokens.push(format!("{}.", number)),
+            PieceColor::Black if interrupted => tokens.push(format!("{}...", number)),
+            PieceColor::Black => {}
+        }
+        tokens.push(node.san.clone());
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        comment_tokens(&node.comments, tokens);
+        for variation in &node.variations {
+            let start = tokens.len();
+            line_tokens(tree, variation, first_ply + i, tokens);
+            // the parentheses are attached to the first and last token of the variation
+            match tokens.get_mut(start) {
+                Some(first) => first.insert(0, '('),
+                None => tokens.push("(".to_string()),
+            }
+            if let Some(last) = tokens.last_mut() {
+                last.push(')');
+            }
+        }
+        interrupted = !node.comments.is_empty() || !node.variations.is_empty();
+    }
+}
+
+/// Appends the comments as single tokens, so their text is written as it was read
+fn comment_tokens(comments: &[String], tokens: &mut Vec<String>) {
+    tokens.extend(comments.iter().map(|comment| format!("{{{}}}", comment)));
+}
+
+/// Joins the tokens with spaces and breaks lines before they exceed `MAX_LINE_LENGTH`.
+/// Tokens are never split, a comment only continues on a new line where its text does.
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        let first_line = token.find('\n').unwrap_or(token.len());
+        if line_length > 0 && line_length + 1 + first_line > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length = match token.rfind('\n') {
+            Some(index) => token.len() - index - 1,
+            None => line_length + token.len(),
+        };
+    }
+    text
+}
+
+/// Describes why a PGN game could not be read
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PgnError {
+    /// The tag pair on the given line is malformed
+    MalformedTag(usize),
+    /// The `FEN` tag does not hold a valid position
+    InvalidFen(FenError),
+    /// The move token at the given move number is malformed, ambiguous or illegal
+    InvalidMove {
+        move_number: usize,
+        color: PieceColor,
+        token: String,
+        error: SanError,
+    },
+    /// A comment or variation is not closed
+    Unterminated(char),
+    /// A variation is closed without being opened or opened before any move
+    Unexpected(char),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::MalformedTag(line) => write!(f, "malformed tag pair on line {}", line),
+            PgnError::InvalidFen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnError::InvalidMove {
+                move_number,
+                color,
+                token,
+                error,
+            } => {
+                let dots = match color {
+                    PieceColor::White => ".",
+                    PieceColor::Black => "...",
+                };
+                write!(f, "{} '{}' at {}{}", error, token, move_number, dots)
+            }
+            PgnError::Unterminated(c) => write!(f, "missing closing '{}'", c),
+            PgnError::Unexpected(c) => write!(f, "unexpected '{}'", c),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// A lexical element of the movetext, move numbers are dropped
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Move(&'a str),
+    Nag(u8),
+    Comment(String),
+    StartVariation,
+    EndVariation,
+    Result(&'a str),
+}
+
+/// Reads the movetext of a game with the given tags, its moves are validated against the rules.
+/// Comments, variations and NAGs are kept in the game tree, suffix annotations like `!` become NAGs.
+pub fn parse_game(tags: Vec<(String, String)>, movetext: &str) -> Result<PgnGame, PgnError> {
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Position::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Position::default(),
+    };
+    let mut tokens = movetext_tokens_of(movetext)?.into_iter().peekable();
+    let mainline = parse_line(&mut tokens, start.clone(), false)?;
+    let outcome = match tokens.next() {
+        Some(Token::Result("1-0")) => Some(GameOutcome::WhiteWins),
+        Some(Token::Result("0-1")) => Some(GameOutcome::BlackWins),
+        Some(Token::Result("1/2-1/2")) => Some(GameOutcome::Draw),
+        _ => None,
+    };
+    Ok(PgnGame {
+        tags,
+        tree: GameTree { start, mainline },
+        outcome,
+    })
+}
+
+/// Parses a tag pair like `[Event "F/S Return Match"]`
+pub fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            _ => unescaped.push(c),
+        }
+    }
+    Some((name.to_string(), unescaped))
+}
+
+/// Splits movetext into tokens, the text of comments is kept as written
+fn movetext_tokens_of(movetext: &str) -> Result<Vec<Token<'_>>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' | ';' => {
+                let (end, skip) = match c {
+                    '{' => (rest.find('}').ok_or(PgnError::Unterminated('}'))?, 1),
+                    _ => (rest.find('\n').unwrap_or(rest.len()), 0),
+                };
+                tokens.push(Token::Comment(rest[1..end].to_string()));
+                rest = &rest[end + skip..];
+            }
+            '(' | ')' => {
+                tokens.push(match c {
+                    '(' => Token::StartVariation,
+                    _ => Token::EndVariation,
+                });
+                rest = &rest[1..];
+            }
+            _ if c.is_whitespace() => rest = &rest[c.len_utf8()..],
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{;()".contains(c))
+                    .unwrap_or(rest.len());
+                let token = &rest[..end];
+                rest = &rest[end..];
+                if RESULTS.contains(&token) {
+                    tokens.push(Token::Result(token));
+                } else if let Some(nag) = token.strip_prefix('$') {
+                    // NAGs range from 0 to 255, anything else is dropped
+                    if let Ok(nag) = nag.parse() {
+                        tokens.push(Token::Nag(nag));
+                    }
+                } else {
+                    let token = strip_move_number(token);
+                    let san = token.trim_end_matches(['!', '?']);
+                    if !san.is_empty() {
+                        tokens.push(Token::Move(san));
+                    }
+                    if let Some(nag) = nag_of_symbol(&token[san.len()..]) {
+                        tokens.push(Token::Nag(nag));
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Removes a move number from the start of the token, which may be followed by the move
+/// without a space like `1.e4`. Digits without a dot are kept, as castling may be written as `0-0`.
+fn strip_move_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    match rest.len() < token.len() && rest.starts_with('.') {
+        true => rest.trim_start_matches('.'),
+        false => token,
+    }
+}
+
+/// Reads the moves and annotations of a line that starts in the given position,
+/// up to the end of the variation or the game termination marker
+fn parse_line(
+    tokens: &mut Peekable<IntoIter<Token<'_>>>,
+    mut position: Position,
+    in_variation: bool,
+) -> Result<Line, PgnError> {
+    let mut line = Line::default();
+    // the position in front of the last move, where its variations start
+    let mut previous = position.clone();
+    while let Some(token) = tokens.next_if(|token| !matches!(token, Token::Result(_))) {
+        match token {
+            Token::Comment(comment) => match line.nodes.last_mut() {
+                Some(node) => node.comments.push(comment),
+                None => line.comments.push(comment),
+            },
+            Token::Nag(nag) => match line.nodes.last_mut() {
+                Some(node) => node.nags.push(nag),
+                None => line.nags.push(nag),
+            },
+            Token::StartVariation => {
+                if line.nodes.is_empty() {
+                    return Err(PgnError::Unexpected('('));
+                }
+                let variation = parse_line(tokens, previous.clone(), true)?;
+                if let Some(node) = line.nodes.last_mut() {
+                    node.variations.push(variation);
+                }
+            }
+            Token::EndVariation if in_variation => return Ok(line),
+            Token::EndVariation => return Err(PgnError::Unexpected(')')),
+            Token::Move(token) => {
+                let mv = position
+                    .parse_san(token)
+                    .map_err(|error| PgnError::InvalidMove {
+                        move_number: position.fullmove_number,
+                        color: position.side_to_move,
+                        token: token.to_string(),
+                        error,
+                    })?;
+                line.nodes.push(GameNode::new(&position, mv));
+                previous = position.clone();
+                position.make_move(mv);
+            }
+            Token::Result(_) => unreachable!(),
+        }
+    }
+    match in_variation {
+        true => Err(PgnError::Unterminated(')')),
+        false => Ok(line),
+    }
+}
+
+/// Returns the current UTC date in the `YYYY.MM.DD` format used by the `Date` tag
+pub fn current_date() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian calendar date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // shift the epoch to 0000-03-01, so leap days are at the end of each 400 year era
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::components::{Location, PieceType};
+    use crate::board::pgn_reader::{PgnReadError, PgnReader};
+    use crate::board::position::Move;
+
+    /// Reads the first game of the given PGN text
+    fn read_pgn(text: &str) -> Result<PgnGame, PgnError> {
+        match PgnReader::new(text.as_bytes()).next() {
+            Some(Ok(raw)) => raw.parse(),
+            Some(Err(PgnReadError::Malformed { error, .. })) => Err(error),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    fn mv(from: &str, to: &str) -> Move {
+        let location = |square: &str| {
+            let bytes = square.as_bytes();
+            Location::new((bytes[0] - b'a') as usize, (bytes[1] - b'1') as usize)
+        };
+        Move::new(location(from), location(to), None)
+    }
+
+    #[test]
+    fn writes_seven_tag_roster_and_movetext() {
+        // fool's mate
+        let moves = [
+            mv("f2", "f3"),
+            mv("e7", "e5"),
+            mv("g2", "g4"),
+            mv("d8", "h4"),
+        ];
+        let headers = PgnHeaders {
+            white: "White \"W\" Player".to_string(),
+            ..Default::default()
+        };
+        let tree = GameTree::from_moves(Position::default(), &moves);
+        let pgn = write_pgn(&PgnGame::new(&headers, tree, Some(GameOutcome::BlackWins)));
+        assert_eq!(
+            pgn,
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"White \\\"W\\\" Player\"]\n[Black \"?\"]\n[Result \"0-1\"]\n\n\
+             1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+    }
+
+    #[test]
+    fn set_up_position_starting_with_black() {
+        let start = Position::from_fen("4k3/P7/8/8/8/8/8/4K2R b K - 0 40").unwrap();
+        let mut promotion = mv("a7", "a8");
+        promotion.promotion = Some(PieceType::Queen);
+        let moves = [mv("e8", "d7"), mv("e1", "g1"), mv("d7", "c7"), promotion];
+        let tree = GameTree::from_moves(start, &moves);
+        let pgn = write_pgn(&PgnGame::new(&PgnHeaders::default(), tree, None));
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/P7/8/8/8/8/8/4K2R b K - 0 40\"]\n"));
+        assert!(pgn.ends_with("\n40... Kd7 41. O-O Kc7 42. a8=Q *\n"));
+    }
+
+    #[test]
+    fn movetext_is_wrapped() {
+        let knight_moves = [
+            mv("g1", "f3"),
+            mv("g8", "f6"),
+            mv("f3", "g1"),
+            mv("f6", "g8"),
+        ];
+        let moves: Vec<Move> = knight_moves.iter().cycle().take(40).copied().collect();
+        let tree = GameTree::from_moves(Position::default(), &moves);
+        let pgn = write_pgn(&PgnGame::new(&PgnHeaders::default(), tree, None));
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(movetext.split_whitespace().count(), 20 + 40 + 1);
+    }
+
+    #[test]
+    fn reads_written_games() {
+        let start = Position::from_fen("4k3/P7/8/8/8/8/8/4K2R b K - 0 40").unwrap();
+        let mut promotion = mv("a7", "a8");
+        promotion.promotion = Some(PieceType::Queen);
+        let moves = vec![mv("e8", "d7"), mv("e1", "g1"), mv("d7", "c7"), promotion];
+        let written = PgnGame::new(
+            &PgnHeaders::default(),
+            GameTree::from_moves(start.clone(), &moves),
+            Some(GameOutcome::Draw),
+        );
+        let game = read_pgn(&write_pgn(&written)).unwrap();
+        assert_eq!(game, written);
+        assert_eq!(game.tree.start, start);
+        assert_eq!(game.tree.mainline_moves(), moves);
+        assert_eq!(game.outcome, Some(GameOutcome::Draw));
+        assert_eq!(game.tag("Result"), Some("1/2-1/2"));
+    }
+
+    #[test]
+    fn reads_comments_variations_and_nags() {
+        let pgn = "[Event \"Test \\\"quoted\\\"\"]\n\n\
+                   {Opening} 1.e4 {best by\n test} e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3! $18 ; comment\n\
+                   Nc6 3. Bb5 a6 *";
+        let game = read_pgn(pgn).unwrap();
+        assert_eq!(game.tag("Event"), Some("Test \"quoted\""));
+        assert_eq!(game.outcome, None);
+
+        let mainline = &game.tree.mainline;
+        assert_eq!(mainline.comments, ["Opening"]);
+        assert_eq!(mainline.nodes.len(), 6);
+        assert_eq!(mainline.nodes[0].comments, ["best by\n test"]);
+        assert_eq!(mainline.nodes[2].nags, [1, 18]);
+        assert_eq!(mainline.nodes[2].comments, [" comment"]);
+
+        let sicilian = &mainline.nodes[1].variations[0];
+        let sans: Vec<_> = sicilian
+            .nodes
+            .iter()
+            .map(|node| node.san.as_str())
+            .collect();
+        assert_eq!(sans, ["c5", "Nf3", "d6"]);
+        assert_eq!(sicilian.nodes[1].variations[0].nodes[0].san, "c3");
+    }
+
+    #[test]
+    fn reads_castling_written_with_zeros() {
+        for movetext in [
+            "1. e4 e5 2. Nf3 Nf6 3. Bc4 Bc5 4. 0-0 0-0 *",
+            "1.e4 e5 2.Nf3 Nf6 3.Bc4 Bc5 4.0-0 4...0-0 *",
+            "1. d4 d5 2. Nc3 Nc6 3. Bf4 Bf5 4. Qd2 Qd7 5. 0-0-0 0-0-0 *",
+            "1.d4 d5 2.Nc3 Nc6 3.Bf4 Bf5 4.Qd2 Qd7 5.0-0-0 5...0-0-0 *",
+        ] {
+            let game = parse_game(Vec::new(), movetext).unwrap();
+            let sans: Vec<_> = game
+                .tree
+                .mainline
+                .nodes
+                .iter()
+                .map(|node| node.san.as_str())
+                .collect();
+            let castling = match movetext.contains("0-0-0") {
+                true => "O-O-O",
+                false => "O-O",
+            };
+            assert_eq!(sans[sans.len() - 2..], [castling, castling], "{}", movetext);
+        }
+    }
+
+    #[test]
+    fn round_trips_annotated_games() {
+        let pgn = "[Event \"?\"]\n[ECO \"B20\"]\n[Result \"1-0\"]\n\n\
+                   {Opening} 1. e4 {best by test} 1... e5 (1... c5 2. Nf3 (2. c3) 2... d6 $2) 2.\n\
+                   Nf3 $1 $18 {comment} 2... Nc6 (2... d6 {Philidor} (2... Nf6) 3. d4) 3. Bb5 a6\n\
+                   1-0\n";
+        let game = read_pgn(pgn).unwrap();
+        assert_eq!(write_pgn(&game), pgn);
+        assert_eq!(read_pgn(&write_pgn(&game)), Ok(game));
+
+        // suffix annotations are written as NAGs
+        let game = read_pgn("1. e4!? e5?? *").unwrap();
+        assert_eq!(write_pgn(&game), "\n1. e4 $5 e5 $4 *\n");
+    }
+
+    #[test]
+    fn round_trips_leading_nags_and_comment_text() {
+        let pgn = "$1 {  two spaces} 1. e4 ($6 {in front of\n    d4} 1. d4 $2) 1... e5 {a\nb} ; rest of line\n*";
+        let game = read_pgn(pgn).unwrap();
+        let mainline = &game.tree.mainline;
+        assert_eq!(mainline.nags, [1]);
+        assert_eq!(mainline.comments, ["  two spaces"]);
+        let variation = &mainline.nodes[0].variations[0];
+        assert_eq!(variation.nags, [6]);
+        assert_eq!(variation.comments, ["in front of\n    d4"]);
+        assert_eq!(variation.nodes[0].nags, [2]);
+        assert_eq!(mainline.nodes[1].comments, ["a\nb", " rest of line"]);
+
+        let written = write_pgn(&game);
+        assert_eq!(
+            written,
+            "\n$1 {  two spaces} 1. e4 ($6 {in front of\n    d4} 1. d4 $2) 1... e5 {a\nb} { rest of line} *\n"
+        );
+        assert_eq!(read_pgn(&written), Ok(game));
+    }
+
+    #[test]
+    fn reports_move_number_and_token() {
+        let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bb5 Bb4 4. O-O-O 1-0";
+        assert_eq!(
+            read_pgn(pgn),
+            Err(PgnError::InvalidMove {
+                move_number: 4,
+                color: PieceColor::White,
+                token: "O-O-O".to_string(),
+                error: SanError::Illegal,
+            })
+        );
+        assert_eq!(
+            read_pgn("1. e4 e5 2. Ke2 Kd5").unwrap_err().to_string(),
+            "illegal move 'Kd5' at 2..."
+        );
+        assert_eq!(read_pgn("[Event]\n1. e4"), Err(PgnError::MalformedTag(1)));
+        assert_eq!(read_pgn("1. e4 {open"), Err(PgnError::Unterminated('}')));
+        assert_eq!(read_pgn("1. e4 (1. d4 *"), Err(PgnError::Unterminated(')')));
+        assert_eq!(read_pgn("(1. d4) 1. e4"), Err(PgnError::Unexpected('(')));
+        assert_eq!(read_pgn("1. e4 e5) 2. d4"), Err(PgnError::Unexpected(')')));
+        assert_eq!(
+            read_pgn("1. e4 (1. d4 d5 2. Nf6)").unwrap_err().to_string(),
+            "illegal move 'Nf6' at 2."
+        );
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_358), (2023, 1, 1));
+    }
+}
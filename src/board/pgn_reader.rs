@@ -0,0 +1,369 @@
+use crate::board::pgn::{self, PgnError, PgnGame, RESULTS};
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// The tags and the unparsed movetext of a game in a PGN database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawGame {
+    /// The byte offset of the first line of the game
+    pub offset: u64,
+    /// All tag pairs in the order they appear
+    pub tags: Vec<(String, String)>,
+    /// Empty if the reader only reads the tags
+    pub movetext: String,
+}
+
+impl RawGame {
+    /// Returns the value of the tag with the given name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the movetext and validates its moves against the rules
+    pub fn parse(&self) -> Result<PgnGame, PgnError> {
+        pgn::parse_game(self.tags.clone(), &self.movetext)
+    }
+}
+
+/// Describes why a game of a PGN database could not be read
+#[derive(Debug)]
+pub enum PgnReadError {
+    /// Reading from the underlying reader failed, no more games are read afterwards
+    Io(io::Error),
+    /// The game starting at the given byte offset is malformed, the reader continues with the next one
+    Malformed { offset: u64, error: PgnError },
+}
+
+impl fmt::Display for PgnReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnReadError::Io(err) => write!(f, "unable to read PGN: {}", err),
+            PgnReadError::Malformed { offset, error } => {
+                write!(f, "malformed game at byte {}: {}", offset, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnReadError {}
+
+/// Reads the games of a PGN database one after another without loading the whole database.
+/// Only the tags of each game are parsed, so they can be listed cheaply.
+pub struct PgnReader<R> {
+    reader: R,
+    /// The byte offset of the next line
+    offset: u64,
+    /// The first line of the next game, which has been read to find the end of the previous one
+    pending: Option<(u64, String)>,
+    buffer: Vec<u8>,
+    failed: bool,
+    /// Whether the movetext is skipped instead of being stored in the games
+    headers_only: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            pending: None,
+            buffer: Vec::new(),
+            failed: false,
+            headers_only: false,
+        }
+    }
+
+    /// Creates a reader that skips the movetext of the games, so they are listed without
+    /// keeping their moves in memory. Their movetext is left empty.
+    pub fn headers_only(reader: R) -> Self {
+        Self {
+            headers_only: true,
+            ..Self::new(reader)
+        }
+    }
+
+    /// Returns an iterator over the parsed games,
+    /// a game with malformed movetext is reported with its offset as well
+    pub fn games(self) -> impl Iterator<Item = Result<PgnGame, PgnReadError>> {
+        self.map(|raw| {
+            let raw = raw?;
+            raw.parse().map_err(|error| PgnReadError::Malformed {
+                offset: raw.offset,
+                error,
+            })
+        })
+    }
+
+    /// Returns the next line without line break and its byte offset
+    fn next_line(&mut self) -> io::Result<Option<(u64, String)>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+        self.buffer.clear();
+        let read = self.reader.read_until(b'\n', &mut self.buffer)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let offset = self.offset;
+        self.offset += read as u64;
+        // PGN databases are often Latin-1 encoded, such characters are replaced
+        let line = String::from_utf8_lossy(&self.buffer);
+        Ok(Some((
+            offset,
+            line.trim_end_matches(['\n', '\r']).to_string(),
+        )))
+    }
+
+    /// Returns the next line that is neither empty nor escaped with `%`
+    fn next_content_line(&mut self) -> io::Result<Option<(u64, String)>> {
+        loop {
+            match self.next_line()? {
+                None => return Ok(None),
+                Some((_, text)) if text.trim().is_empty() || text.starts_with('%') => {}
+                Some(line) => return Ok(Some(line)),
+            }
+        }
+    }
+
+    /// Reads the lines of the next game, which ends before the next tag section,
+    /// at an empty line after the game termination marker or at the end of the database.
+    /// A game without termination marker also ends at an empty line followed by a tag
+    /// or a move number, which starts the next game.
+    fn read_game(&mut self) -> io::Result<Option<Result<RawGame, PgnReadError>>> {
+        // lines starting with `%` are escaped and ignored
+        let mut line = self.next_content_line()?;
+        let offset = match &line {
+            Some((offset, _)) => *offset,
+            None => return Ok(None),
+        };
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        let mut has_movetext = false;
+        let mut terminated = false;
+        let mut error = None;
+        let mut number = 0;
+        // a brace comment may span several lines, which are part of the movetext whatever they look like
+        let mut in_comment = false;
+        while let Some((line_offset, text)) = line.take() {
+            number += 1;
+            let trimmed = text.trim();
+            if !in_comment && trimmed.starts_with('[') {
+                if has_movetext {
+                    self.pending = Some((line_offset, text));
+                    break;
+                }
+                match pgn::parse_tag(trimmed) {
+                    Some(tag) => tags.push(tag),
+                    None => {
+                        error.get_or_insert(PgnError::MalformedTag(number));
+                    }
+                }
+            } else if !in_comment && trimmed.is_empty() {
+                if terminated {
+                    break;
+                }
+                if has_movetext {
+                    // the empty lines in between belong to neither game
+                    let next = self.next_content_line()?;
+                    let starts_game = next.as_ref().is_some_and(|(_, text)| {
+                        let trimmed = text.trim_start();
+                        trimmed.starts_with('[') || starts_with_move_number(trimmed)
+                    });
+                    self.pending = next;
+                    if starts_game {
+                        break;
+                    }
+                }
+            } else if in_comment || !text.starts_with('%') {
+                in_comment = is_comment_open(&text, in_comment);
+                if let Some(token) = text.split_whitespace().last() {
+                    terminated = !in_comment && RESULTS.contains(&token);
+                }
+                has_movetext = true;
+                if !self.headers_only {
+                    movetext.push_str(&text);
+                    movetext.push('\n');
+                }
+            }
+            line = self.next_line()?;
+        }
+        Ok(Some(match error {
+            Some(error) => Err(PgnReadError::Malformed { offset, error }),
+            None => Ok(RawGame {
+                offset,
+                tags,
+                movetext,
+            }),
+        }))
+    }
+}
+
+/// Returns whether the text starts with a move number like `12.` or `12...`,
+/// a result like `1-0` does not count
+fn starts_with_move_number(text: &str) -> bool {
+    let rest = text.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.len() < text.len() && rest.starts_with('.')
+}
+
+/// Returns whether a brace comment is open at the end of the given movetext line,
+/// `open` tells whether one was open at its start
+fn is_comment_open(line: &str, mut open: bool) -> bool {
+    for c in line.chars() {
+        match (open, c) {
+            (true, '}') => open = false,
+            (false, '{') => open = true,
+            // the rest of the line is a comment, so braces in it have no meaning
+            (false, ';') => break,
+            _ => {}
+        }
+    }
+    open
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<RawGame, PgnReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.read_game() {
+            Ok(game) => game,
+            Err(err) => {
+                self.failed = true;
+                Some(Err(PgnReadError::Io(err)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::pgn::PgnError;
+    use crate::board::san::SanError;
+
+    const DATABASE: &str = "[Event \"First\"]\n[White \"A\"]\n\n1. e4 e5 1-0\n\n\
+                            [Event \"Broken tag]\n\n1. d4 d5 *\n\n\
+                            [Event \"Illegal\"]\n\n1. e4 e4 *\n\
+                            [Event \"Last\"]\n1. c4 {no empty line in front} *\n\n\
+                            1. Nf3 *\n";
+
+    #[test]
+    fn reads_headers_of_every_game() {
+        let games: Vec<_> = PgnReader::new(DATABASE.as_bytes()).collect();
+        assert_eq!(games.len(), 5);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.offset, 0);
+        assert_eq!(first.tag("White"), Some("A"));
+        assert_eq!(first.movetext, "1. e4 e5 1-0\n");
+        let offset = DATABASE.find("[Event \"Broken").unwrap() as u64;
+        assert!(matches!(
+            games[1],
+            Err(PgnReadError::Malformed {
+                offset: malformed,
+                error: PgnError::MalformedTag(1)
+            }) if malformed == offset
+        ));
+        assert_eq!(games[3].as_ref().unwrap().tag("Event"), Some("Last"));
+        // games without tags are separated by the termination marker
+        assert_eq!(games[4].as_ref().unwrap().movetext, "1. Nf3 *\n");
+    }
+
+    #[test]
+    fn skips_malformed_games() {
+        let games: Vec<_> = PgnReader::new(DATABASE.as_bytes()).games().collect();
+        assert_eq!(games.len(), 5);
+        assert_eq!(games[0].as_ref().unwrap().tree.mainline.nodes.len(), 2);
+        let offset = DATABASE.find("[Event \"Illegal\"]").unwrap() as u64;
+        match &games[2] {
+            Err(PgnReadError::Malformed {
+                offset: malformed,
+                error: PgnError::InvalidMove { token, error, .. },
+            }) => {
+                assert_eq!(*malformed, offset);
+                assert_eq!(token, "e4");
+                assert_eq!(*error, SanError::Illegal);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(games[3].as_ref().unwrap().tree.mainline.nodes.len(), 1);
+        assert!(games[4].is_ok());
+    }
+
+    #[test]
+    fn ends_unterminated_games_at_the_next_game() {
+        let database = "[Event \"Unterminated\"]\n\n1. e4 e5\n\n{continued}\n2. Nf3\n\n\n\
+                        1. d4 e4\n\n[Event \"Last\"]\n\n1. c4\n";
+        let games: Vec<_> = PgnReader::new(database.as_bytes()).collect();
+        assert_eq!(games.len(), 3);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.movetext, "1. e4 e5\n{continued}\n2. Nf3\n");
+        let second = games[1].as_ref().unwrap();
+        assert_eq!(second.offset, database.find("1. d4").unwrap() as u64);
+        assert!(second.tags.is_empty());
+        assert_eq!(second.movetext, "1. d4 e4\n");
+        assert_eq!(games[2].as_ref().unwrap().tag("Event"), Some("Last"));
+
+        let games: Vec<_> = PgnReader::new(database.as_bytes()).games().collect();
+        assert!(matches!(
+            games[1],
+            Err(PgnReadError::Malformed { offset, .. }) if offset == second.offset
+        ));
+    }
+
+    #[test]
+    fn skips_movetext_when_reading_headers_only() {
+        let games: Vec<_> = PgnReader::new(DATABASE.as_bytes()).collect();
+        let headers: Vec<_> = PgnReader::headers_only(DATABASE.as_bytes()).collect();
+        assert_eq!(headers.len(), games.len());
+        for (header, game) in headers.iter().zip(&games) {
+            match (header, game) {
+                (Ok(header), Ok(game)) => {
+                    assert_eq!(header.offset, game.offset);
+                    assert_eq!(header.tags, game.tags);
+                    assert!(header.movetext.is_empty());
+                }
+                (
+                    Err(PgnReadError::Malformed { offset, error }),
+                    Err(PgnReadError::Malformed {
+                        offset: expected_offset,
+                        error: expected_error,
+                    }),
+                ) => {
+                    assert_eq!(offset, expected_offset);
+                    assert_eq!(error, expected_error);
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn replaces_invalid_characters() {
+        // `Réti` in Latin-1
+        let database = b"[White \"R\xe9ti\"]\n\n1. Nf3 *\n";
+        let game = PgnReader::new(&database[..]).next().unwrap().unwrap();
+        assert_eq!(game.tag("White"), Some("R\u{fffd}ti"));
+    }
+
+    #[test]
+    fn reads_comments_spanning_lines() {
+        let database = "[Event \"x\"]\n\n1. e4 {a\n[%clk 0:01]} e5 *\n\n\
+                        [Event \"y\"]\n\n1. d4 {\n\n%not escaped\n} d5 ; {\n1-0\n";
+        let games: Vec<_> = PgnReader::new(database.as_bytes()).collect();
+        assert_eq!(games.len(), 2);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.movetext, "1. e4 {a\n[%clk 0:01]} e5 *\n");
+        let game = first.parse().unwrap();
+        assert_eq!(game.tree.mainline.nodes.len(), 2);
+        assert_eq!(game.tree.mainline.nodes[0].comments, ["a\n[%clk 0:01]"]);
+
+        let second = games[1].as_ref().unwrap();
+        assert_eq!(second.tag("Event"), Some("y"));
+        assert_eq!(second.parse().unwrap().tree.mainline.nodes.len(), 2);
+    }
+}
@@ -1,31 +1,58 @@
-use crate::board::components::PieceColor;
 use crate::board::events::{
-    CheckedPieceMoveEvent, PieceSelectionEvent, PlayedMoveEvent, UncheckedPieceMoveEvent,
+    CheckedPieceMoveEvent, ClaimDrawEvent, EngineEvent, GameOverEvent, LoadGameEvent,
+    PieceSelectionEvent, PlayMoveEvent, PlayedMoveEvent, ReplayEvent, UncheckedPieceMoveEvent,
 };
+use crate::board::position::Position;
+use crate::board::rules::{DrawRules, PositionHistory};
 use crate::board::systems::{
-    handle_checked_move_events, handle_square_status_updates, handle_unchecked_move_events,
-    record_played_moves, selection,
+    detect_game_over, handle_checked_move_events, handle_claim_draw_events,
+    handle_game_over_events, handle_load_game_events, handle_play_move_events,
+    handle_square_status_updates, handle_unchecked_move_events, record_played_moves, selection,
 };
-use crate::board::systems::{input, startup};
-use crate::board::{CurrentPlayer, PlayedMoves};
+use crate::board::systems::{engine, input, replay, startup};
+use crate::board::{CapturedPieces, PlayedMoves};
 use bevy::prelude::*;
 
 pub struct BoardPlugin;
 
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<PlayedMoves>()
-            .insert_resource(CurrentPlayer(PieceColor::White))
+        let position = Position::default();
+        app.insert_resource(PositionHistory(vec![position.zobrist_key()]))
+            .insert_resource(position)
+            .init_resource::<PlayedMoves>()
+            .init_resource::<CapturedPieces>()
+            .init_resource::<DrawRules>()
             .add_startup_system(startup::setup_board)
             .add_event::<PieceSelectionEvent>()
             .add_event::<UncheckedPieceMoveEvent>()
             .add_event::<CheckedPieceMoveEvent>()
+            .add_event::<PlayMoveEvent>()
             .add_event::<PlayedMoveEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<ClaimDrawEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_event::<ReplayEvent>()
+            .add_event::<EngineEvent>()
             .add_system(input::left_click_piece_selection)
             .add_system(selection::handle_piece_selection_events)
             .add_system(handle_square_status_updates)
             .add_system(handle_unchecked_move_events)
             .add_system(handle_checked_move_events)
-            .add_system(record_played_moves);
+            .add_system(handle_play_move_events)
+            .add_system(
+                record_played_moves
+                    .after(handle_checked_move_events)
+                    .after(handle_play_move_events),
+            )
+            .add_system(handle_claim_draw_events)
+            .add_system(handle_game_over_events)
+            .add_system(replay::autoplay_replay)
+            // the game of the reached replay position has to be loaded in the same frame
+            .add_system(replay::handle_replay_events.after(replay::autoplay_replay))
+            .add_system(handle_load_game_events.after(replay::handle_replay_events))
+            .add_system(engine::handle_engine_events)
+            .add_system(engine::finish_engine_search.before(handle_play_move_events))
+            .add_system_to_stage(CoreStage::PostUpdate, detect_game_over);
     }
 }
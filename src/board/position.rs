@@ -0,0 +1,446 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::components::{CastlingSide, Location, Piece, PieceColor, PieceType};
+use crate::board::{attacks, movegen, zobrist, PlayedMove};
+
+/// A move of a piece from one square to another,
+/// castling is represented by the king move and en passant by the pawn move to the skipped square
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Move {
+    pub from: Location,
+    pub to: Location,
+    /// The piece type a pawn is promoted to when reaching the last rank
+    pub promotion: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Location, to: Location, promotion: Option<PieceType>) -> Self {
+        Self {
+            from,
+            to,
+            promotion,
+        }
+    }
+}
+
+/// Holds the castling rights for both sides, a right gets lost
+/// as soon as the king or the corresponding rook has moved or the rook has been captured
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+}
+
+impl CastlingRights {
+    /// Returns castling rights where neither side is allowed to castle
+    pub fn none() -> Self {
+        Self {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+
+    /// Returns true if the given color still has the right to castle to the given side
+    pub fn can_castle(&self, color: &PieceColor, side: &CastlingSide) -> bool {
+        match (color, side) {
+            (PieceColor::White, CastlingSide::Kingside) => self.white_kingside,
+            (PieceColor::White, CastlingSide::Queenside) => self.white_queenside,
+            (PieceColor::Black, CastlingSide::Kingside) => self.black_kingside,
+            (PieceColor::Black, CastlingSide::Queenside) => self.black_queenside,
+        }
+    }
+
+    /// Removes the right to castle to the given side for the given color
+    pub fn revoke(&mut self, color: &PieceColor, side: &CastlingSide) {
+        match (color, side) {
+            (PieceColor::White, CastlingSide::Kingside) => self.white_kingside = false,
+            (PieceColor::White, CastlingSide::Queenside) => self.white_queenside = false,
+            (PieceColor::Black, CastlingSide::Kingside) => self.black_kingside = false,
+            (PieceColor::Black, CastlingSide::Queenside) => self.black_queenside = false,
+        }
+    }
+
+    /// Updates the castling rights for a move from `source` to `target`.
+    /// Any move from or to the initial king or rook squares revokes the affected rights.
+    pub fn update_for_move(&mut self, source: &Location, target: &Location) {
+        for color in [PieceColor::White, PieceColor::Black] {
+            let rank = color.home_rank();
+            for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+                let king = Location::new(4, rank);
+                let rook = Location::new(side.rook_source_file(), rank);
+                if [king, rook]
+                    .iter()
+                    .any(|loc| loc == source || loc == target)
+                {
+                    self.revoke(&color, &side);
+                }
+            }
+        }
+    }
+}
+
+/// The part of the position state that cannot be restored from a move alone when taking it back
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IrreversibleState {
+    pub castling_rights: CastlingRights,
+    pub en_passant: Option<Location>,
+    pub halfmove_clock: usize,
+    /// The zobrist key of the position before the move
+    pub key: u64,
+}
+
+/// A chess position that is independent from the ECS and holds the full game state,
+/// the board entities only mirror it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Position {
+    squares: [Option<Piece>; 64],
+    /// The occupied squares per color, indexed by `PieceColor::index`
+    colors: [Bitboard; 2],
+    /// The occupied squares per piece type of both colors, indexed by `PieceType::index`
+    kinds: [Bitboard; 6],
+    /// The zobrist key of the position, updated whenever a piece is placed or removed and by `make_move`.
+    /// Changing the state fields directly requires `reset_key` afterwards.
+    key: u64,
+    pub side_to_move: PieceColor,
+    pub castling_rights: CastlingRights,
+    /// The square a pawn skipped with a two square push in the last move
+    pub en_passant: Option<Location>,
+    /// The number of halfmoves since the last capture or pawn move
+    pub halfmove_clock: usize,
+    /// The number of the full move, starting at 1 and incremented after each move of black
+    pub fullmove_number: usize,
+}
+
+impl Default for Position {
+    /// Returns the initial position
+    fn default() -> Self {
+        let mut position = Position::empty();
+        let back_rank = [
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ];
+        for (x, kind) in back_rank.into_iter().enumerate() {
+            position.set(Location::new(x, 0), Piece::new(kind, PieceColor::White));
+            position.set(
+                Location::new(x, 1),
+                Piece::new(PieceType::Pawn, PieceColor::White),
+            );
+            position.set(
+                Location::new(x, 6),
+                Piece::new(PieceType::Pawn, PieceColor::Black),
+            );
+            position.set(Location::new(x, 7), Piece::new(kind, PieceColor::Black));
+        }
+        position.castling_rights = CastlingRights::default();
+        position.reset_key();
+        position
+    }
+}
+
+impl Position {
+    /// Returns a position without any pieces and castling rights where white is to move
+    pub fn empty() -> Self {
+        Self {
+            squares: [None; 64],
+            colors: [Bitboard::EMPTY; 2],
+            kinds: [Bitboard::EMPTY; 6],
+            key: 0,
+            side_to_move: PieceColor::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    /// Returns the piece placed on the given location, if any
+    pub fn piece_at(&self, location: &Location) -> Option<Piece> {
+        self.squares[location.index()]
+    }
+
+    /// Places the given piece on the given location and replaces any existing piece
+    pub fn set(&mut self, location: Location, piece: Piece) {
+        self.remove(&location);
+        let square = Bitboard::from_location(&location);
+        self.colors[piece.color.index()] |= square;
+        self.kinds[piece.kind.index()] |= square;
+        self.key ^= zobrist::piece(&piece, &location);
+        self.squares[location.index()] = Some(piece);
+    }
+
+    /// Removes the piece placed on the given location and returns it
+    pub fn remove(&mut self, location: &Location) -> Option<Piece> {
+        let piece = self.squares[location.index()].take()?;
+        let square = !Bitboard::from_location(location);
+        self.colors[piece.color.index()] &= square;
+        self.kinds[piece.kind.index()] &= square;
+        self.key ^= zobrist::piece(&piece, location);
+        Some(piece)
+    }
+
+    /// Returns all occupied squares
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Returns all squares occupied by pieces of the given color
+    pub fn occupied_by(&self, color: &PieceColor) -> Bitboard {
+        self.colors[color.index()]
+    }
+
+    /// Returns all squares occupied by pieces of the given type and color
+    pub fn pieces_of(&self, kind: PieceType, color: &PieceColor) -> Bitboard {
+        self.kinds[kind.index()] & self.colors[color.index()]
+    }
+
+    /// Returns all pieces together with their locations
+    pub fn pieces(&self) -> Vec<(Piece, Location)> {
+        self.squares
+            .iter()
+            .enumerate()
+            .filter_map(|(index, piece)| piece.map(|piece| (piece, Location::from_index(index))))
+            .collect()
+    }
+
+    /// Returns the 64-bit zobrist key of this position, which covers the pieces, side to move,
+    /// castling rights and the en passant file if a pawn of the side to move can capture on it
+    pub fn zobrist_key(&self) -> u64 {
+        self.key
+    }
+
+    /// Recomputes the zobrist key from scratch after the side to move, castling rights
+    /// or en passant square have been changed without playing a move
+    pub fn reset_key(&mut self) {
+        self.key = self.compute_key();
+    }
+
+    fn compute_key(&self) -> u64 {
+        self.pieces().iter().fold(0, |key, (piece, location)| {
+            key ^ zobrist::piece(piece, location)
+        }) ^ zobrist::side_to_move(&self.side_to_move)
+            ^ zobrist::castling(&self.castling_rights)
+            ^ self.en_passant_key()
+    }
+
+    /// Returns the key of the en passant file if a pawn of the side to move can capture on it,
+    /// otherwise the en passant square makes no difference and 0 is returned
+    fn en_passant_key(&self) -> u64 {
+        let en_passant = match self.en_passant {
+            Some(en_passant) => en_passant,
+            None => return 0,
+        };
+        let attackers = attacks::pawn_attacks(&self.side_to_move.opponent(), &en_passant)
+            & self.pieces_of(PieceType::Pawn, &self.side_to_move);
+        match attackers.is_empty() {
+            true => 0,
+            false => zobrist::en_passant(en_passant.x),
+        }
+    }
+
+    /// Returns the location of the king of the given color
+    pub fn king_location(&self, color: &PieceColor) -> Option<Location> {
+        self.pieces_of(PieceType::King, color).first()
+    }
+
+    /// Plays the given move and returns what has been changed on the board.
+    /// The move is expected to be legal in this position.
+    pub fn make_move(&mut self, mv: Move) -> PlayedMove {
+        let piece = self
+            .piece_at(&mv.from)
+            .expect("there must be a piece on the source square");
+        let previous = IrreversibleState {
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+        };
+        // the keys of the castling rights and en passant square are added again after the move
+        self.key ^= zobrist::castling(&self.castling_rights) ^ self.en_passant_key();
+
+        // a pawn moving diagonally to an empty square captures en passant
+        let en_passant = piece.kind == PieceType::Pawn
+            && mv.from.x != mv.to.x
+            && self.piece_at(&mv.to).is_none();
+        let captured = match en_passant {
+            true => self.remove(&Location::new(mv.to.x, mv.from.y)),
+            false => self.remove(&mv.to),
+        };
+
+        let mut moved = piece;
+        moved.has_moved = true;
+        if let Some(kind) = mv.promotion {
+            moved.kind = kind;
+        }
+        self.remove(&mv.from);
+        self.set(mv.to, moved);
+
+        // a king moving two squares castles, so the rook has to be moved as well
+        let castling = match piece.kind {
+            PieceType::King => CastlingSide::from_king_move(mv.from.x, mv.to.x),
+            _ => None,
+        };
+        if let Some(side) = castling {
+            let rook_source = Location::new(side.rook_source_file(), mv.from.y);
+            let rook_target = Location::new(side.rook_target_file(), mv.from.y);
+            let mut rook = self.remove(&rook_source).expect("castling requires a rook");
+            rook.has_moved = true;
+            self.set(rook_target, rook);
+        }
+
+        self.castling_rights.update_for_move(&mv.from, &mv.to);
+        self.en_passant = match piece.kind {
+            PieceType::Pawn if mv.from.y.abs_diff(mv.to.y) == 2 => {
+                Some(Location::new(mv.from.x, (mv.from.y + mv.to.y) / 2))
+            }
+            _ => None,
+        };
+        self.halfmove_clock = match piece.kind == PieceType::Pawn || captured.is_some() {
+            true => 0,
+            false => self.halfmove_clock + 1,
+        };
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opponent();
+        // the black to move key is toggled by every move
+        self.key ^= zobrist::side_to_move(&PieceColor::Black)
+            ^ zobrist::castling(&self.castling_rights)
+            ^ self.en_passant_key();
+
+        PlayedMove {
+            piece,
+            source: mv.from,
+            target: mv.to,
+            captured,
+            castling,
+            en_passant,
+            promotion: mv.promotion,
+            previous,
+        }
+    }
+
+    /// Takes back the given move, which has to be the last move played on this position.
+    /// Restores captured pieces, castling rights, en passant state, clocks and `has_moved` flags.
+    pub fn unmake_move(&mut self, played: &PlayedMove) {
+        self.side_to_move = self.side_to_move.opponent();
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number -= 1;
+        }
+        self.castling_rights = played.previous.castling_rights;
+        self.en_passant = played.previous.en_passant;
+        self.halfmove_clock = played.previous.halfmove_clock;
+
+        if let Some(side) = played.castling {
+            let rank = played.source.y;
+            let mut rook = self
+                .remove(&Location::new(side.rook_target_file(), rank))
+                .expect("castling moved a rook");
+            // castling is only allowed with a rook that has not moved before
+            rook.has_moved = false;
+            self.set(Location::new(side.rook_source_file(), rank), rook);
+        }
+        self.remove(&played.target);
+        self.set(played.source, played.piece);
+        if let (Some(captured), Some(location)) = (played.captured, played.captured_location()) {
+            self.set(location, captured);
+        }
+        self.key = played.previous.key;
+    }
+
+    /// Returns all legal moves for the side to move
+    pub fn legal_moves(&self) -> Vec<Move> {
+        movegen::legal_moves(self)
+    }
+
+    /// Returns all legal moves for the piece on the given location
+    pub fn legal_moves_from(&self, location: &Location) -> Vec<Move> {
+        match self.piece_at(location) {
+            Some(piece) if piece.color == self.side_to_move => self
+                .legal_moves()
+                .into_iter()
+                .filter(|mv| &mv.from == location)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns true if the king of the given color is attacked by any opponent piece
+    pub fn is_in_check(&self, color: &PieceColor) -> bool {
+        match self.king_location(color) {
+            Some(king) => movegen::is_attacked(self, &king, &color.opponent()),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays every move up to the given depth and checks that taking it back restores the position
+    fn assert_unmake_restores(position: &mut Position, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        for mv in position.legal_moves() {
+            let before = position.clone();
+            let played = position.make_move(mv);
+            assert_eq!(position.zobrist_key(), position.compute_key(), "{:?}", mv);
+            assert_unmake_restores(position, depth - 1);
+            position.unmake_move(&played);
+            assert_eq!(*position, before, "{:?}", mv);
+            assert_eq!(position.zobrist_key(), before.zobrist_key());
+        }
+    }
+
+    #[test]
+    fn unmake_restores_position() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            assert_unmake_restores(&mut Position::from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn updates_key_incrementally() {
+        let mut position = Position::default();
+        // castling, a double push allowing en passant, the capture and a promotion
+        for uci in [
+            "e2e4", "g8f6", "e4e5", "d7d5", "e5d6", "e7e6", "g1f3", "f8e7", "f1c4", "e8g8", "e1g1",
+            "f6d5", "d6c7", "d5c3", "c7b8q",
+        ] {
+            let mv = position.parse_uci(uci).unwrap();
+            position.make_move(mv);
+            assert_eq!(position.zobrist_key(), position.compute_key(), "{}", uci);
+        }
+        let fen = position.to_fen();
+        assert_eq!(
+            position.zobrist_key(),
+            Position::from_fen(&fen).unwrap().zobrist_key()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::board::components::PieceType;
+use crate::board::position::Position;
+use crate::board::utils::square_color;
+use crate::board::{GameOverReason, GameResult};
+
+/// Halfmoves without capture or pawn move after which a draw can be claimed
+const FIFTY_MOVE_RULE_HALFMOVES: usize = 100;
+/// Halfmoves without capture or pawn move after which the game is drawn in any case
+const SEVENTY_FIVE_MOVE_RULE_HALFMOVES: usize = 150;
+
+/// Configures how draws by repetition and the fifty-move rule are handled
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DrawRules {
+    /// Ends the game as soon as a draw could be claimed, otherwise the player has to claim it.
+    /// Fivefold repetition and the seventy-five-move rule always end the game.
+    pub automatic: bool,
+}
+
+impl Default for DrawRules {
+    fn default() -> Self {
+        Self { automatic: true }
+    }
+}
+
+/// Holds the reason a draw can currently be claimed by the player to move
+pub struct ClaimableDraw(pub GameOverReason);
+
+/// Holds the zobrist keys of all positions that occurred in the current game, including the initial one.
+/// Two positions are the same if the same pieces occupy the same squares,
+/// the same side is to move and castling and en passant rights are equal.
+#[derive(Default)]
+pub struct PositionHistory(pub Vec<u64>);
+
+impl PositionHistory {
+    /// Returns how often the most recent position occurred
+    pub fn repetitions(&self) -> usize {
+        match self.0.last() {
+            Some(current) => self.0.iter().filter(|key| *key == current).count(),
+            None => 0,
+        }
+    }
+}
+
+/// Returns true if neither side has enough material left to deliver checkmate,
+/// which covers king versus king, a single minor piece or bishops on same colored squares
+pub fn is_insufficient_material(position: &Position) -> bool {
+    let mut knights = 0;
+    let mut bishop_square_colors = Vec::new();
+    for (piece, location) in position.pieces() {
+        match piece.kind {
+            PieceType::King => {}
+            PieceType::Knight => knights += 1,
+            PieceType::Bishop => bishop_square_colors.push(square_color(location.x, location.y)),
+            _ => return false,
+        }
+    }
+    match (knights, bishop_square_colors.as_slice()) {
+        (0, []) | (1, []) => true,
+        (0, [first, rest @ ..]) => rest.iter().all(|color| color == first),
+        _ => false,
+    }
+}
+
+/// The draw state of a position
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DrawStatus {
+    /// The game is drawn for the given reason
+    Drawn(GameOverReason),
+    /// The player to move may claim a draw for the given reason
+    Claimable(GameOverReason),
+}
+
+/// The state of a game after a move has been played
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    /// The player to move may claim a draw for the given reason or continue the game
+    Claimable(GameOverReason),
+    Over(GameResult),
+}
+
+/// Checks whether the game is over by checkmate, stalemate or one of the draw rules
+pub fn game_status(
+    position: &Position,
+    history: &PositionHistory,
+    rules: &DrawRules,
+) -> GameStatus {
+    if let Some(result) = GameResult::detect(position) {
+        return GameStatus::Over(result);
+    }
+    match detect_draw(position, history, rules) {
+        Some(DrawStatus::Drawn(reason)) => GameStatus::Over(GameResult::draw(reason)),
+        Some(DrawStatus::Claimable(reason)) => GameStatus::Claimable(reason),
+        None => GameStatus::Ongoing,
+    }
+}
+
+/// Checks the draw rules for the current position and returns whether the game is drawn
+/// or a draw can be claimed. Claimable draws end the game immediately if `automatic` is enabled.
+pub fn detect_draw(
+    position: &Position,
+    history: &PositionHistory,
+    rules: &DrawRules,
+) -> Option<DrawStatus> {
+    if is_insufficient_material(position) {
+        return Some(DrawStatus::Drawn(GameOverReason::InsufficientMaterial));
+    }
+
+    let repetitions = history.repetitions();
+    let halfmove_clock = position.halfmove_clock;
+    if repetitions >= 5 {
+        return Some(DrawStatus::Drawn(GameOverReason::FivefoldRepetition));
+    }
+    if halfmove_clock >= SEVENTY_FIVE_MOVE_RULE_HALFMOVES {
+        return Some(DrawStatus::Drawn(GameOverReason::SeventyFiveMoveRule));
+    }
+
+    let claimable = if repetitions >= 3 {
+        GameOverReason::ThreefoldRepetition
+    } else if halfmove_clock >= FIFTY_MOVE_RULE_HALFMOVES {
+        GameOverReason::FiftyMoveRule
+    } else {
+        return None;
+    };
+    match rules.automatic {
+        true => Some(DrawStatus::Drawn(claimable)),
+        false => Some(DrawStatus::Claimable(claimable)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+    use crate::board::GameOutcome;
+
+    const CLAIMABLE: DrawRules = DrawRules { automatic: false };
+    const AUTOMATIC: DrawRules = DrawRules { automatic: true };
+
+    fn is_insufficient(fen: &str) -> bool {
+        is_insufficient_material(&Position::from_fen(fen).unwrap())
+    }
+
+    /// Plays the given UCI moves and returns the final position with the keys of all positions on the way
+    fn play(fen: &str, moves: &[&str]) -> (Position, PositionHistory) {
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut history = PositionHistory(vec![position.zobrist_key()]);
+        for uci in moves {
+            let mv = position.parse_uci(uci).unwrap();
+            position.make_move(mv);
+            history.0.push(position.zobrist_key());
+        }
+        (position, history)
+    }
+
+    fn status(fen: &str, rules: &DrawRules) -> GameStatus {
+        let (position, history) = play(fen, &[]);
+        game_status(&position, &history, rules)
+    }
+
+    #[test]
+    fn detects_insufficient_material() {
+        assert!(is_insufficient("8/8/4k3/8/8/4K3/8/8 w - - 0 1"));
+        assert!(is_insufficient("8/8/4k3/8/8/3BK3/8/8 w - - 0 1"));
+        assert!(is_insufficient("8/8/4k3/8/8/3NK3/8/8 w - - 0 1"));
+        assert!(!is_insufficient("8/8/4k3/8/8/2NNK3/8/8 w - - 0 1"));
+        assert!(!is_insufficient("8/8/4k3/8/8/3PK3/8/8 w - - 0 1"));
+        assert!(!is_insufficient("8/8/4k3/8/8/3RK3/8/8 w - - 0 1"));
+        assert!(!is_insufficient("8/8/4k1n1/8/8/3BK3/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn compares_bishop_square_colors() {
+        // d3 and e6 are both light squares, no matter which side the bishops belong to
+        assert!(is_insufficient("7k/8/4b3/8/8/3BK3/8/8 w - - 0 1"));
+        assert!(is_insufficient("7k/8/4B3/8/8/3BK3/8/8 w - - 0 1"));
+        // d3 is light and d6 is dark
+        assert!(!is_insufficient("7k/8/3b4/8/8/3BK3/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn applies_move_rules() {
+        let fen = |halfmoves: usize| format!("4k3/8/8/8/8/8/3R4/4K3 w - - {} 80", halfmoves);
+        assert_eq!(status(&fen(99), &AUTOMATIC), GameStatus::Ongoing);
+        assert_eq!(
+            status(&fen(100), &CLAIMABLE),
+            GameStatus::Claimable(GameOverReason::FiftyMoveRule)
+        );
+        assert_eq!(
+            status(&fen(100), &AUTOMATIC),
+            GameStatus::Over(GameResult::draw(GameOverReason::FiftyMoveRule))
+        );
+        assert_eq!(
+            status(&fen(150), &CLAIMABLE),
+            GameStatus::Over(GameResult::draw(GameOverReason::SeventyFiveMoveRule))
+        );
+    }
+
+    #[test]
+    fn checkmate_takes_precedence_over_move_rules() {
+        let status = status("R3k3/8/4K3/8/8/8/8/8 b - - 100 80", &AUTOMATIC);
+        assert_eq!(
+            status,
+            GameStatus::Over(GameResult {
+                outcome: GameOutcome::WhiteWins,
+                reason: GameOverReason::Checkmate,
+            })
+        );
+    }
+
+    #[test]
+    fn applies_repetition_rules() {
+        let cycle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let moves = |cycles: usize| cycle.repeat(cycles);
+
+        let (position, history) = play(INITIAL_FEN, &moves(1));
+        assert_eq!(history.repetitions(), 2);
+        assert_eq!(
+            game_status(&position, &history, &CLAIMABLE),
+            GameStatus::Ongoing
+        );
+
+        let (position, history) = play(INITIAL_FEN, &moves(2));
+        assert_eq!(history.repetitions(), 3);
+        assert_eq!(
+            game_status(&position, &history, &CLAIMABLE),
+            GameStatus::Claimable(GameOverReason::ThreefoldRepetition)
+        );
+        assert_eq!(
+            game_status(&position, &history, &AUTOMATIC),
+            GameStatus::Over(GameResult::draw(GameOverReason::ThreefoldRepetition))
+        );
+
+        let (position, history) = play(INITIAL_FEN, &moves(4));
+        assert_eq!(history.repetitions(), 5);
+        assert_eq!(
+            game_status(&position, &history, &CLAIMABLE),
+            GameStatus::Over(GameResult::draw(GameOverReason::FivefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn distinguishes_castling_rights_in_repetitions() {
+        // the positions after the king returns lack the castling rights of the initial one
+        let (_, history) = play(
+            "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1",
+            &[
+                "e1f1", "e8d8", "f1e1", "d8e8", "e1f1", "e8d8", "f1e1", "d8e8",
+            ],
+        );
+        assert_eq!(history.repetitions(), 2);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::board::components::{CastlingSide, File, Location, PieceType};
+use crate::board::position::{Move, Position};
+use crate::board::utils;
+use std::fmt;
+
+/// Describes why a SAN string could not be turned into a move
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SanError {
+    /// The string is not a move in algebraic notation
+    Malformed,
+    /// No legal move in the position matches
+    Illegal,
+    /// Several legal moves match, the source square needs to be given
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            SanError::Malformed => "malformed move",
+            SanError::Illegal => "illegal move",
+            SanError::Ambiguous => "ambiguous move",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for SanError {}
+
+/// The parts of a SAN move that are checked against the legal moves
+struct SanMove {
+    kind: PieceType,
+    /// File and rank of the source square as far as they are given
+    from_file: Option<usize>,
+    from_rank: Option<usize>,
+    to: Location,
+    promotion: Option<PieceType>,
+}
+
+impl Position {
+    /// Returns the given move in Standard Algebraic Notation,
+    /// the move is expected to be legal in this position
+    pub fn san(&self, mv: &Move) -> String {
+        let piece = self
+            .piece_at(&mv.from)
+            .expect("there must be a piece on the source square");
+        let castling = match piece.kind {
+            PieceType::King => CastlingSide::from_king_move(mv.from.x, mv.to.x),
+            _ => None,
+        };
+
+        let mut san = match castling {
+            Some(side) => side.notation().to_string(),
+            None => {
+                let capture = self.piece_at(&mv.to).is_some()
+                    || (piece.kind == PieceType::Pawn && mv.from.x != mv.to.x);
+                let mut san = String::new();
+                match piece.kind {
+                    PieceType::Pawn if capture => {
+                        san.push_str(&File::from_index(mv.from.x).to_string())
+                    }
+                    PieceType::Pawn => {}
+                    _ => {
+                        san.push(piece.notation());
+                        san.push_str(&self.disambiguation(mv, piece.kind));
+                    }
+                }
+                if capture {
+                    san.push('x');
+                }
+                san.push_str(&mv.to.to_string());
+                if let Some(kind) = mv.promotion {
+                    san.push('=');
+                    san.push(kind.notation());
+                }
+                san
+            }
+        };
+
+        let mut after = self.clone();
+        after.make_move(*mv);
+        if after.is_in_check(&after.side_to_move) {
+            match after.legal_moves().is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+        }
+        san
+    }
+
+    /// Parses the given SAN into the matching legal move.
+    /// Zeros for castling, missing or superfluous check markers, annotations like `!?`
+    /// and an `e.p.` suffix are accepted.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let san = strip_suffixes(san.trim());
+        let castling = match san.replace('0', "O").as_str() {
+            "O-O" => Some(CastlingSide::Kingside),
+            "O-O-O" => Some(CastlingSide::Queenside),
+            _ => None,
+        };
+        let candidates: Vec<Move> = match castling {
+            Some(side) => self
+                .legal_moves()
+                .into_iter()
+                .filter(|mv| {
+                    matches!(self.piece_at(&mv.from), Some(piece) if piece.kind == PieceType::King)
+                        && CastlingSide::from_king_move(mv.from.x, mv.to.x) == Some(side)
+                })
+                .collect(),
+            None => {
+                let parsed = parse_san_move(san).ok_or(SanError::Malformed)?;
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|mv| {
+                        mv.to == parsed.to
+                            && mv.promotion == parsed.promotion
+                            && parsed.from_file.is_none_or(|file| mv.from.x == file)
+                            && parsed.from_rank.is_none_or(|rank| mv.from.y == rank)
+                            && matches!(self.piece_at(&mv.from), Some(piece) if piece.kind == parsed.kind)
+                    })
+                    .collect()
+            }
+        };
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(SanError::Illegal),
+            _ => Err(SanError::Ambiguous),
+        }
+    }
+
+    /// Returns the source file, rank or square needed to tell the given move apart
+    /// from moves of other pieces of the same type to the same target
+    fn disambiguation(&self, mv: &Move, kind: PieceType) -> String {
+        let others: Vec<_> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| other.to == mv.to && other.from != mv.from)
+            .filter(|other| matches!(self.piece_at(&other.from), Some(piece) if piece.kind == kind))
+            .map(|other| other.from)
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|from| from.x != mv.from.x) {
+            File::from_index(mv.from.x).to_string()
+        } else if others.iter().all(|from| from.y != mv.from.y) {
+            utils::rank_for_index(mv.from.y).to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+}
+
+/// Removes check markers, annotations and the en passant suffix from the end of the given SAN
+fn strip_suffixes(mut san: &str) -> &str {
+    loop {
+        let stripped = san
+            .trim_end_matches(['+', '#', '!', '?'])
+            .trim_end_matches("e.p.")
+            .trim_end();
+        if stripped == san {
+            return san;
+        }
+        san = stripped;
+    }
+}
+
+/// Splits a SAN move without suffixes like `Nbxd7` or `exd8=Q` into its parts
+fn parse_san_move(san: &str) -> Option<SanMove> {
+    let (kind, rest) = match san.chars().next()? {
+        letter if letter.is_ascii_uppercase() => (PieceType::from_notation(letter)?, &san[1..]),
+        _ => (PieceType::Pawn, san),
+    };
+
+    // the promotion piece follows the target square, the equals sign is optional
+    let (rest, promotion) = match rest.char_indices().last()? {
+        (index, letter) if letter.is_ascii_uppercase() => {
+            let promotion = PieceType::from_notation(letter)?;
+            (
+                rest[..index].strip_suffix('=').unwrap_or(&rest[..index]),
+                Some(promotion),
+            )
+        }
+        _ => (rest, None),
+    };
+    if let Some(promotion) = promotion {
+        if kind != PieceType::Pawn || !PieceType::PROMOTIONS.contains(&promotion) {
+            return None;
+        }
+    }
+
+    let split = rest.len().checked_sub(2)?;
+    if !rest.is_char_boundary(split) {
+        return None;
+    }
+    let to = Location::from_notation(&rest[split..])?;
+    // the capture marker is optional, a dash is accepted between the squares of long notation
+    let source = rest[..split].trim_end_matches(['x', ':', '-']);
+    let (from_file, from_rank) = match source.as_bytes() {
+        [] => (None, None),
+        [file @ b'a'..=b'h'] => (Some((file - b'a') as usize), None),
+        [rank @ b'1'..=b'8'] => (None, Some((rank - b'1') as usize)),
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            (Some((file - b'a') as usize), Some((rank - b'1') as usize))
+        }
+        _ => return None,
+    };
+    Some(SanMove {
+        kind,
+        from_file,
+        from_rank,
+        to,
+        promotion,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::components::{Location, PieceType};
+    use crate::board::position::{Move, Position};
+    use crate::board::san::SanError;
+
+    /// Returns the SAN of the legal move between the given squares
+    fn san(fen: &str, from: &str, to: &str, promotion: Option<PieceType>) -> String {
+        let position = Position::from_fen(fen).unwrap();
+        let mv = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| {
+                mv.from.to_string() == from && mv.to.to_string() == to && mv.promotion == promotion
+            })
+            .expect("the move must be legal");
+        position.san(&mv)
+    }
+
+    #[test]
+    fn pieces_and_pawns() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(fen, "e2", "e4", None), "e4");
+        assert_eq!(san(fen, "g1", "f3", None), "Nf3");
+    }
+
+    #[test]
+    fn captures() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/2N5/PPPP1PPP/R1BQKBNR w KQkq - 0 2";
+        assert_eq!(san(fen, "e4", "d5", None), "exd5");
+        assert_eq!(san(fen, "c3", "d5", None), "Nxd5");
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        assert_eq!(san(fen, "e5", "f6", None), "exf6");
+    }
+
+    #[test]
+    fn disambiguation() {
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(san(fen, "b1", "d2", None), "Nbd2");
+        assert_eq!(san(fen, "f1", "d2", None), "Nfd2");
+        let fen = "R7/7k/8/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(fen, "a1", "a4", None), "R1a4");
+        assert_eq!(san(fen, "a8", "a4", None), "R8a4");
+        let fen = "1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(fen, "h4", "e1", None), "Qh4e1");
+        // a pinned knight can not move to the same square
+        let fen = "4k3/8/8/b7/8/8/3N4/4K1N1 w - - 0 1";
+        assert_eq!(san(fen, "g1", "f3", None), "Nf3");
+    }
+
+    #[test]
+    fn castling_and_promotion() {
+        let fen = "r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1";
+        assert_eq!(san(fen, "e1", "g1", None), "O-O");
+        assert_eq!(san(fen, "e1", "c1", None), "O-O-O");
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "b7", "b8", Some(PieceType::Knight)), "b8=N");
+        assert_eq!(san(fen, "b7", "a8", Some(PieceType::Queen)), "bxa8=Q+");
+    }
+
+    #[test]
+    fn check_and_checkmate() {
+        let fen = "5k2/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(san(fen, "e1", "g1", None), "O-O+");
+        // fool's mate
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2";
+        assert_eq!(san(fen, "d8", "h4", None), "Qh4#");
+    }
+
+    /// Parses the given SAN and returns the move in long algebraic notation like `e7e8q`
+    fn parse(fen: &str, san: &str) -> Result<String, SanError> {
+        let position = Position::from_fen(fen).unwrap();
+        position.parse_san(san).map(|mv| mv.to_uci())
+    }
+
+    #[test]
+    fn parses_generated_san() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1",
+        ];
+        for fen in fens {
+            let position = Position::from_fen(fen).unwrap();
+            for mv in position.legal_moves() {
+                assert_eq!(position.parse_san(&position.san(&mv)), Ok(mv));
+            }
+        }
+    }
+
+    #[test]
+    fn parses_common_variants() {
+        let fen = "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+        assert_eq!(parse(fen, "O-O"), Ok("e1g1".to_string()));
+        assert_eq!(parse(fen, "0-0-0"), Ok("e1c1".to_string()));
+        assert_eq!(parse(fen, "exd6 e.p."), Ok("e5d6".to_string()));
+        assert_eq!(parse(fen, "exd6e.p.+"), Ok("e5d6".to_string()));
+        assert_eq!(parse(fen, "ed6"), Ok("e5d6".to_string()));
+        assert_eq!(parse(fen, "Ra1-a8+!"), Ok("a1a8".to_string()));
+        assert_eq!(parse(fen, "Rxa8"), Ok("a1a8".to_string()));
+        let fen = "3r4/4P3/8/8/8/8/8/k3K3 w - - 0 1";
+        assert_eq!(parse(fen, "e8=N"), Ok("e7e8n".to_string()));
+        assert_eq!(parse(fen, "exd8Q"), Ok("e7d8q".to_string()));
+    }
+
+    #[test]
+    fn reports_invalid_san() {
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(parse(fen, "Nd2"), Err(SanError::Ambiguous));
+        assert_eq!(parse(fen, "Nd3"), Err(SanError::Illegal));
+        assert_eq!(parse(fen, "O-O"), Err(SanError::Illegal));
+        for san in ["", "N", "Nz9", "Xd2", "Nbb1d2", "e4=K", "Nd2=Q", "x"] {
+            assert_eq!(parse(fen, san), Err(SanError::Malformed), "{}", san);
+        }
+        // a promotion needs the piece
+        let fen = "8/4P3/8/8/8/8/8/k3K3 w - - 0 1";
+        assert_eq!(parse(fen, "e8"), Err(SanError::Illegal));
+        let position = Position::from_fen(fen).unwrap();
+        let mv = Move::new(
+            Location::new(4, 6),
+            Location::new(4, 7),
+            Some(PieceType::Rook),
+        );
+        assert_eq!(position.parse_san("e8=R"), Ok(mv));
+    }
+}
@@ -0,0 +1,393 @@
+use crate::board::components::PieceType;
+use crate::board::evaluation::{evaluate, piece_value};
+use crate::board::position::{Move, Position};
+use std::cmp::Reverse;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// The score of being checkmated at the root, mates found deeper in the tree are closer to zero
+/// so the shortest mate is preferred
+pub const MATE: i32 = 30_000;
+const INFINITY: i32 = MATE + 1;
+/// The deepest ply the search can reach, mate scores are within this distance to `MATE`
+const MAX_PLY: usize = 128;
+/// The number of nodes between checks whether the search has to stop
+const STOP_CHECK_INTERVAL: u64 = 2048;
+
+/// Limits how long a search runs, the first iteration always completes to have a move to play
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SearchLimits {
+    /// The depth of the last iteration in plies
+    pub depth: usize,
+    /// The time after which the search stops and returns the result of the last complete iteration
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: usize) -> Self {
+        Self { depth, time: None }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            depth: MAX_PLY / 2,
+            time: Some(time),
+        }
+    }
+}
+
+/// The outcome of a complete iteration of the search
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SearchResult {
+    /// The move to play, which is `None` if the game is already over
+    pub best_move: Option<Move>,
+    /// The score in centipawns from the view of the side to move, see `mate_in` for mate scores
+    pub score: i32,
+    pub depth: usize,
+    /// The principal variation, the line the search expects to be played starting with `best_move`
+    pub pv: Vec<Move>,
+    /// The number of positions visited during the whole search
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// Returns the number of moves until the side to move mates,
+    /// which is negative if the side to move gets mated
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE - self.score.abs();
+        if plies > MAX_PLY as i32 {
+            return None;
+        }
+        match self.score > 0 {
+            true => Some((plies + 1) / 2),
+            false => Some(-plies / 2),
+        }
+    }
+}
+
+impl fmt::Display for SearchResult {
+    /// Formats the result like the `info` output of UCI engines
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {} score ", self.depth)?;
+        match self.mate_in() {
+            Some(moves) => write!(f, "mate {}", moves)?,
+            None => write!(f, "cp {}", self.score)?,
+        }
+        write!(f, " nodes {} pv", self.nodes)?;
+        for mv in &self.pv {
+            write!(f, " {}", mv)?;
+        }
+        Ok(())
+    }
+}
+
+/// Searches the best move with an alpha-beta negamax search and iterative deepening.
+/// `history` holds the zobrist keys of the previous positions of the game to detect repetitions.
+/// Setting `stop` ends the search early, `on_iteration` is called with every complete iteration.
+pub fn search(
+    position: &Position,
+    history: &[u64],
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    let mut searcher = Searcher {
+        keys: history.to_vec(),
+        stop,
+        deadline: limits.time.map(|time| Instant::now() + time),
+        stoppable: false,
+        stopped: false,
+        nodes: 0,
+        previous_pv: Vec::new(),
+    };
+    let key = position.zobrist_key();
+    if searcher.keys.last() != Some(&key) {
+        searcher.keys.push(key);
+    }
+
+    let mut position = position.clone();
+    let mut result = SearchResult::default();
+    for depth in 1..=limits.depth.clamp(1, MAX_PLY) {
+        searcher.stoppable = depth > 1;
+        if searcher.stoppable && searcher.must_stop() {
+            break;
+        }
+        let mut pv = Vec::new();
+        let score = searcher.negamax(&mut position, depth, 0, -INFINITY, INFINITY, &mut pv);
+        if searcher.stopped {
+            break;
+        }
+        result = SearchResult {
+            best_move: pv.first().copied(),
+            score,
+            depth,
+            pv: pv.clone(),
+            nodes: searcher.nodes,
+        };
+        on_iteration(&result);
+        searcher.previous_pv = pv;
+        // deeper iterations cannot find a shorter mate or a way out of a forced one,
+        // and there is nothing to search if the game is over
+        if result.mate_in().is_some() || result.best_move.is_none() {
+            break;
+        }
+    }
+    result.nodes = searcher.nodes;
+    result
+}
+
+struct Searcher<'a> {
+    /// The zobrist keys of the game and the positions on the path to the current node
+    keys: Vec<u64>,
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    /// Whether an iteration may be cut off, the first one has to complete
+    stoppable: bool,
+    stopped: bool,
+    nodes: u64,
+    /// The principal variation of the previous iteration, which is searched first
+    previous_pv: Vec<Move>,
+}
+
+impl Searcher<'_> {
+    /// Returns the score of the position from the view of the side to move and stores
+    /// the best line in `pv`, the score is only exact if it is between `alpha` and `beta`
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: usize,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if self.should_stop() {
+            return 0;
+        }
+        if ply > 0 && self.is_draw(position) {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(position, alpha, beta);
+        }
+        self.nodes += 1;
+
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return match position.is_in_check(&position.side_to_move) {
+                true => -MATE + ply as i32,
+                false => 0,
+            };
+        }
+        self.order_moves(position, &mut moves, self.previous_pv.get(ply).copied());
+
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            let played = position.make_move(mv);
+            self.keys.push(position.zobrist_key());
+            let score = -self.negamax(position, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.keys.pop();
+            position.unmake_move(&played);
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+
+    /// Searches captures and promotions until the position is quiet,
+    /// so the evaluation is not taken in the middle of an exchange
+    fn quiescence(&mut self, position: &mut Position, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        // the side to move is not forced to capture, so the evaluation is a lower bound
+        let stand_pat = evaluate(position);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<Move> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| is_capture(position, mv) || mv.promotion.is_some())
+            .collect();
+        self.order_moves(position, &mut moves, None);
+        for mv in moves {
+            let played = position.make_move(mv);
+            let score = -self.quiescence(position, -beta, -alpha);
+            position.unmake_move(&played);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Sorts the moves so that the most promising ones are searched first, which are the move
+    /// of the previous principal variation and then captures of valuable pieces by cheap ones
+    fn order_moves(&self, position: &Position, moves: &mut [Move], pv_move: Option<Move>) {
+        moves.sort_by_cached_key(|mv| {
+            if Some(*mv) == pv_move {
+                return Reverse(i32::MAX);
+            }
+            let mut score = 0;
+            if is_capture(position, mv) {
+                let victim = position
+                    .piece_at(&mv.to)
+                    .map_or(PieceType::Pawn, |piece| piece.kind);
+                let attacker = position.piece_at(&mv.from).expect("moves start at a piece");
+                score += 10 * piece_value(victim) - piece_value(attacker.kind);
+            }
+            if let Some(promotion) = mv.promotion {
+                score += piece_value(promotion);
+            }
+            Reverse(score)
+        });
+    }
+
+    /// Returns whether the position is drawn by the fifty-move rule or a repetition,
+    /// a single repetition is enough as the same moves could be played again
+    fn is_draw(&self, position: &Position) -> bool {
+        if position.halfmove_clock >= 100 {
+            return true;
+        }
+        let (current, previous) = self.keys.split_last().expect("the root key is always set");
+        previous
+            .iter()
+            .rev()
+            .take(position.halfmove_clock)
+            .any(|key| key == current)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.stoppable && self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) {
+            self.stopped = self.must_stop();
+        }
+        self.stopped
+    }
+
+    /// Returns whether the search has been stopped from outside or ran out of time
+    fn must_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+fn is_capture(position: &Position, mv: &Move) -> bool {
+    if position.piece_at(&mv.to).is_some() {
+        return true;
+    }
+    // a pawn moving diagonally to an empty square captures en passant
+    mv.from.x != mv.to.x
+        && position
+            .piece_at(&mv.from)
+            .is_some_and(|piece| piece.kind == PieceType::Pawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+
+    fn search_fen(fen: &str, depth: usize) -> SearchResult {
+        let position = Position::from_fen(fen).unwrap();
+        search(
+            &position,
+            &[],
+            &SearchLimits::depth(depth),
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    }
+
+    #[test]
+    fn finds_mates() {
+        let result = search_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4);
+        assert_eq!(result.best_move.map(Move::to_uci).as_deref(), Some("a1a8"));
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.mate_in(), Some(1));
+
+        // the king has to take away the flight squares before the rook can mate
+        let result = search_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1", 4);
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.pv.len(), 3);
+        let mut position = Position::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        result.pv.iter().for_each(|mv| {
+            position.make_move(*mv);
+        });
+        assert!(position.legal_moves().is_empty());
+        assert!(position.is_in_check(&position.side_to_move));
+
+        let result = search_fen("6k1/5ppp/8/8/8/8/r7/r5K1 w - - 0 1", 2);
+        assert_eq!(result.mate_in(), Some(0));
+        assert_eq!(result.best_move, None);
+    }
+
+    #[test]
+    fn wins_material() {
+        // the knight on c7 forks king and queen
+        let result = search_fen("q3k3/8/8/1N6/8/8/8/4K3 w - - 0 1", 3);
+        assert_eq!(result.best_move.map(Move::to_uci).as_deref(), Some("b5c7"));
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn scores_draws() {
+        let result = search_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.score, 0);
+        assert_eq!(result.best_move, None);
+
+        // every move without capture or pawn move reaches the fifty-move rule
+        let result = search_fen("8/8/4k3/8/8/3NK3/8/8 w - - 99 80", 2);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn tracks_the_principal_variation() {
+        let result = search_fen(INITIAL_FEN, 4);
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+        let mut position = Position::from_fen(INITIAL_FEN).unwrap();
+        for mv in &result.pv {
+            assert!(position.legal_moves().contains(mv));
+            position.make_move(*mv);
+        }
+    }
+
+    #[test]
+    fn stops_after_the_first_iteration() {
+        let position = Position::from_fen(INITIAL_FEN).unwrap();
+        let mut iterations = 0;
+        let result = search(
+            &position,
+            &[],
+            &SearchLimits::depth(10),
+            &AtomicBool::new(true),
+            |_| iterations += 1,
+        );
+        assert_eq!(iterations, 1);
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+}
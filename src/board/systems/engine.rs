@@ -0,0 +1,46 @@
+use crate::board::events::{EngineEvent, PlayMoveEvent};
+use crate::board::position::Position;
+use crate::board::rules::PositionHistory;
+use crate::board::EngineSearch;
+use crate::some_or_return;
+use bevy::prelude::*;
+use std::sync::atomic::Ordering;
+
+/// Handles `EngineEvent` to start a search in the current position or to stop the running one
+pub fn handle_engine_events(
+    mut commands: Commands,
+    engine_search: Option<Res<EngineSearch>>,
+    position: Res<Position>,
+    position_history: Res<PositionHistory>,
+    mut engine_reader: EventReader<EngineEvent>,
+) {
+    for event in engine_reader.iter() {
+        match (event, &engine_search) {
+            (EngineEvent::Start, None) => {
+                commands.insert_resource(EngineSearch::start(&position, &position_history.0))
+            }
+            (EngineEvent::Stop, Some(engine_search)) => {
+                engine_search.stop.store(true, Ordering::Relaxed)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Plays the best move once the engine has finished searching, unless the position has changed meanwhile
+pub fn finish_engine_search(
+    mut commands: Commands,
+    engine_search: Option<Res<EngineSearch>>,
+    position: Res<Position>,
+    mut play_move_writer: EventWriter<PlayMoveEvent>,
+) {
+    let engine_search = some_or_return!(engine_search);
+    let result = some_or_return!(engine_search.take_result());
+    commands.remove_resource::<EngineSearch>();
+    if engine_search.key != position.zobrist_key() {
+        return;
+    }
+    let best_move = some_or_return!(result.best_move);
+    println!("INFO: engine plays {}", position.san(&best_move));
+    play_move_writer.send(PlayMoveEvent(best_move));
+}
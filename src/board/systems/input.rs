@@ -1,6 +1,7 @@
 use crate::board::components::Square;
 use crate::board::events::{PieceSelectionEvent, UncheckedPieceMoveEvent};
-use crate::board::{utils, CurrentPlayer, SelectedPiece};
+use crate::board::position::Position;
+use crate::board::{utils, EngineSearch, GameResult, PendingPromotion, Replay, SelectedPiece};
 use crate::{some_or_return, BoardCamera, Location, Piece};
 use bevy::prelude::*;
 
@@ -10,8 +11,12 @@ pub fn left_click_piece_selection(
     squares_q: Query<(Entity, &Children, &Location, &GlobalTransform), With<Square>>,
     cameras_q: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
     selected_piece: Option<Res<SelectedPiece>>,
+    pending_promotion: Option<Res<PendingPromotion>>,
+    game_result: Option<Res<GameResult>>,
+    replay: Option<Res<Replay>>,
+    engine_search: Option<Res<EngineSearch>>,
     mouse_button_input: Res<Input<MouseButton>>,
-    current_player: Res<CurrentPlayer>,
+    position: Res<Position>,
     windows: Res<Windows>,
     mut piece_selection_writer: EventWriter<PieceSelectionEvent>,
     mut moves_writer: EventWriter<UncheckedPieceMoveEvent>,
@@ -21,6 +26,26 @@ pub fn left_click_piece_selection(
         return;
     }
 
+    // the board is blocked until the player has chosen a promotion piece
+    if pending_promotion.is_some() {
+        return;
+    }
+
+    // no more moves are accepted once the game is over
+    if game_result.is_some() {
+        return;
+    }
+
+    // the replayed game is only changed with the replay controls
+    if replay.is_some() {
+        return;
+    }
+
+    // the engine is about to play the next move
+    if engine_search.is_some() {
+        return;
+    }
+
     let cursor = some_or_return!(utils::translate_cursor_pos(cameras_q, windows));
     for (square_entity, square_children, square_location, square_transform) in squares_q.iter() {
         if !utils::intersects_square(&cursor, &square_transform.translation()) {
@@ -44,8 +69,8 @@ pub fn left_click_piece_selection(
             }
             None => {
                 let (entity, comp) = some_or_return!(piece);
-                if current_player.0 != comp.color {
-                    println!("INFO: It's {}'s turn", current_player.0);
+                if position.side_to_move != comp.color {
+                    println!("INFO: It's {}'s turn", position.side_to_move);
                     break;
                 }
                 piece_selection_writer.send(PieceSelectionEvent::Selected(entity));
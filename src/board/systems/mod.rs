@@ -1,17 +1,25 @@
+pub mod engine;
 pub mod input;
+pub mod replay;
 pub mod selection;
 pub mod startup;
 
-use crate::board::components::{Location, Piece, PossibleTarget, Selected, Square};
+use crate::board::components::{Board, Location, Piece, PossibleTarget, Selected, Square};
 use crate::board::events::{
-    CheckedPieceMoveEvent, MoveTarget, PieceSelectionEvent, PlayedMoveEvent,
-    UncheckedPieceMoveEvent,
+    CheckedPieceMoveEvent, ClaimDrawEvent, GameOverEvent, LoadGameEvent, MoveTarget,
+    PieceSelectionEvent, PlayMoveEvent, PlayedMoveEvent, UncheckedPieceMoveEvent,
 };
+use crate::board::position::{Move, Position};
+use crate::board::rules::{ClaimableDraw, DrawRules, GameStatus, PositionHistory};
 use crate::board::utils::square_color;
-use crate::board::{utils, CurrentPlayer, PlayedMove, PlayedMoves};
+use crate::board::{
+    rules, utils, CapturedPieces, GameResult, PendingPromotion, PlayedMoves, Replay, SelectedPiece,
+};
 use crate::constants::PIECE_Z_AXIS;
-use crate::ok_or_return;
+use crate::resources::PieceTheme;
+use crate::{ok_or_return, some_or_return};
 use bevy::prelude::*;
+use bevy_svg::prelude::*;
 
 /// Draws `Square` based on their components
 pub fn handle_square_status_updates(
@@ -29,6 +37,7 @@ pub fn handle_square_status_updates(
 
 /// Handles `UncheckedPieceMoveEvent` and checks if this is a legal move
 pub fn handle_unchecked_move_events(
+    mut commands: Commands,
     pieces_q: Query<&Piece>,
     square_q: Query<(&Children, &Location), With<Square>>,
     mut unchecked_moves: EventReader<UncheckedPieceMoveEvent>,
@@ -48,6 +57,14 @@ pub fn handle_unchecked_move_events(
 
         // check if target square makes up a legal move
         if event.selected.possible_targets.contains(ns_location) {
+            // wait until the player has chosen the piece to promote to
+            if event.selected.is_promotion(ns_location) && event.promotion.is_none() {
+                commands.insert_resource(PendingPromotion {
+                    selected: event.selected.clone(),
+                    target: event.target,
+                });
+                continue;
+            }
             checked_moves.send(CheckedPieceMoveEvent::legal(event));
         } else {
             checked_moves.send(CheckedPieceMoveEvent::illegal(event));
@@ -55,14 +72,18 @@ pub fn handle_unchecked_move_events(
     }
 }
 
-/// Handles `CheckedPieceMoveEvent`
+/// Handles `CheckedPieceMoveEvent`, plays legal moves on the `Position` and mirrors them to the entities
 pub fn handle_checked_move_events(
     mut commands: Commands,
     location_q: Query<&Location>,
+    pieces_q: Query<&Piece>,
     square_q: Query<&GlobalTransform, With<Square>>,
+    squares_q: Query<(Entity, &Location, Option<&Children>), With<Square>>,
     possible_targets_q: Query<Entity, With<PossibleTarget>>,
     mut selected_q: Query<&mut GlobalTransform, (With<Selected>, Without<Square>)>,
-    mut current_player: ResMut<CurrentPlayer>,
+    mut position: ResMut<Position>,
+    piece_theme: Res<PieceTheme>,
+    svgs: Res<Assets<Svg>>,
     mut checked_moves_reader: EventReader<CheckedPieceMoveEvent>,
     mut played_moves_writer: EventWriter<PlayedMoveEvent>,
 ) {
@@ -74,14 +95,25 @@ pub fn handle_checked_move_events(
             // as selected until a new move begins
             MoveTarget::Legal(target) => {
                 let loc_comp = *location_q.get(target).unwrap();
-                utils::update_entity_for_move(&mut commands, event, target, loc_comp);
+                let mv = Move::new(event.selected.location_comp, loc_comp, event.promotion);
+                // the notation depends on the position before the move
+                let san = position.san(&mv);
+                let played_move = position.make_move(mv);
+                utils::mirror_move(
+                    &mut commands,
+                    &played_move,
+                    &squares_q,
+                    &pieces_q,
+                    &piece_theme,
+                    &svgs,
+                );
 
                 // trigger event that this move has been played
-                played_moves_writer.send(PlayedMoveEvent(PlayedMove::from_event(
-                    event, target, loc_comp,
-                )));
-
-                current_player.switch();
+                played_moves_writer.send(PlayedMoveEvent {
+                    played_move,
+                    san,
+                    key: position.zobrist_key(),
+                });
 
                 commands.entity(target).insert(Selected);
                 utils::deselect_piece(&mut commands, event.selected.piece);
@@ -105,13 +137,198 @@ pub fn handle_checked_move_events(
     }
 }
 
-/// Handles `PlayedMoveEvent` to display them
+/// Handles `PlayMoveEvent`, plays the move on the `Position` and mirrors it to the entities
+/// like a move made with the mouse. A piece selected meanwhile is deselected.
+pub fn handle_play_move_events(
+    mut commands: Commands,
+    pieces_q: Query<&Piece>,
+    squares_q: Query<(Entity, &Location, Option<&Children>), With<Square>>,
+    selected_q: Query<Entity, With<Selected>>,
+    possible_targets_q: Query<Entity, With<PossibleTarget>>,
+    mut position: ResMut<Position>,
+    piece_theme: Res<PieceTheme>,
+    svgs: Res<Assets<Svg>>,
+    game_result: Option<Res<GameResult>>,
+    replay: Option<Res<Replay>>,
+    mut play_move_reader: EventReader<PlayMoveEvent>,
+    mut played_moves_writer: EventWriter<PlayedMoveEvent>,
+) {
+    // only one move is played per frame because the board entities are
+    // not updated until the commands of this system have been applied
+    let PlayMoveEvent(mv) = some_or_return!(play_move_reader.iter().next());
+
+    // no more moves are accepted once the game is over
+    if game_result.is_some() {
+        return;
+    }
+
+    // the replayed game is only changed with the replay controls
+    if replay.is_some() {
+        return;
+    }
+
+    if !position.legal_moves().contains(mv) {
+        println!("illegal move");
+        return;
+    }
+
+    selected_q.for_each(|entity| {
+        commands.entity(entity).remove::<Selected>();
+    });
+    possible_targets_q.for_each(|entity| {
+        commands.entity(entity).despawn_recursive();
+    });
+    commands.remove_resource::<SelectedPiece>();
+    commands.remove_resource::<PendingPromotion>();
+
+    // the notation depends on the position before the move
+    let san = position.san(mv);
+    let played_move = position.make_move(*mv);
+    utils::mirror_move(
+        &mut commands,
+        &played_move,
+        &squares_q,
+        &pieces_q,
+        &piece_theme,
+        &svgs,
+    );
+    // mark source and target squares like a move made with the mouse
+    for location in [played_move.source, played_move.target] {
+        if let Some((square, _)) = utils::resolve_square(&location, &squares_q, &pieces_q) {
+            commands.entity(square).insert(Selected);
+        }
+    }
+    played_moves_writer.send(PlayedMoveEvent {
+        played_move,
+        san,
+        key: position.zobrist_key(),
+    });
+}
+
+/// Handles `PlayedMoveEvent` to display them and keeps track of captured pieces
 pub fn record_played_moves(
     mut played_moves: ResMut<PlayedMoves>,
+    mut captured_pieces: ResMut<CapturedPieces>,
     mut moves_reader: EventReader<PlayedMoveEvent>,
 ) {
     for event in moves_reader.iter() {
-        played_moves.0.push(event.0);
-        println!("DEBUG: {}", event.0.notation());
+        played_moves.push(event.played_move, event.san.clone());
+        if let Some(captured) = event.played_move.captured {
+            captured_pieces.push(event.played_move.piece.color, captured);
+        }
+        println!("DEBUG: {}", event.san);
+    }
+}
+
+/// Handles `PlayedMoveEvent` to check whether the side to move has been checkmated or stalemated
+/// and whether any draw rule applies to the new position.
+/// Runs after all moves of the current frame have been played.
+pub fn detect_game_over(
+    mut commands: Commands,
+    position: Res<Position>,
+    draw_rules: Res<DrawRules>,
+    mut position_history: ResMut<PositionHistory>,
+    mut moves_reader: EventReader<PlayedMoveEvent>,
+    mut game_over_writer: EventWriter<GameOverEvent>,
+) {
+    for event in moves_reader.iter() {
+        position_history.0.push(event.key);
+
+        // a claimable draw is only valid for the move right after it arose
+        commands.remove_resource::<ClaimableDraw>();
+        check_game_over(
+            &mut commands,
+            &position,
+            &position_history,
+            &draw_rules,
+            &mut game_over_writer,
+        );
+    }
+}
+
+/// Checks whether the game is over in the given position and inserts the `GameResult`,
+/// or `ClaimableDraw` if the player to move may claim a draw
+fn check_game_over(
+    commands: &mut Commands,
+    position: &Position,
+    position_history: &PositionHistory,
+    draw_rules: &DrawRules,
+    game_over_writer: &mut EventWriter<GameOverEvent>,
+) {
+    match rules::game_status(position, position_history, draw_rules) {
+        GameStatus::Over(result) => {
+            commands.insert_resource(result);
+            game_over_writer.send(GameOverEvent(result));
+        }
+        GameStatus::Claimable(reason) => commands.insert_resource(ClaimableDraw(reason)),
+        GameStatus::Ongoing => {}
+    }
+}
+
+/// Handles `LoadGameEvent`, replays the moves from the start position to rebuild the game state
+/// and respawns the board for the resulting position
+pub fn handle_load_game_events(
+    mut commands: Commands,
+    board_q: Query<Entity, With<Board>>,
+    piece_theme: Res<PieceTheme>,
+    draw_rules: Res<DrawRules>,
+    mut position: ResMut<Position>,
+    mut position_history: ResMut<PositionHistory>,
+    mut played_moves: ResMut<PlayedMoves>,
+    mut captured_pieces: ResMut<CapturedPieces>,
+    mut load_game_reader: EventReader<LoadGameEvent>,
+    mut game_over_writer: EventWriter<GameOverEvent>,
+) {
+    // only the last game matters if several have been loaded at once
+    let event = some_or_return!(load_game_reader.iter().last());
+    board_q.for_each(|board| commands.entity(board).despawn_recursive());
+    commands.remove_resource::<SelectedPiece>();
+    commands.remove_resource::<PendingPromotion>();
+    commands.remove_resource::<GameResult>();
+    commands.remove_resource::<ClaimableDraw>();
+
+    *position = event.start.clone();
+    position_history.0 = vec![position.zobrist_key()];
+    played_moves.reset(event.start.clone());
+    *captured_pieces = CapturedPieces::default();
+    for mv in &event.moves {
+        let san = position.san(mv);
+        let played_move = position.make_move(*mv);
+        position_history.0.push(position.zobrist_key());
+        played_moves.push(played_move, san);
+        if let Some(captured) = played_move.captured {
+            captured_pieces.push(played_move.piece.color, captured);
+        }
+    }
+    startup::spawn_board(&mut commands, &piece_theme, &position);
+    check_game_over(
+        &mut commands,
+        &position,
+        &position_history,
+        &draw_rules,
+        &mut game_over_writer,
+    );
+}
+
+/// Handles `ClaimDrawEvent` and ends the game if a draw can be claimed
+pub fn handle_claim_draw_events(
+    mut commands: Commands,
+    claimable_draw: Option<Res<ClaimableDraw>>,
+    mut claim_draw_reader: EventReader<ClaimDrawEvent>,
+    mut game_over_writer: EventWriter<GameOverEvent>,
+) {
+    for _ in claim_draw_reader.iter() {
+        let claimable = some_or_return!(claimable_draw.as_ref());
+        let result = GameResult::draw(claimable.0);
+        commands.remove_resource::<ClaimableDraw>();
+        commands.insert_resource(result);
+        game_over_writer.send(GameOverEvent(result));
+    }
+}
+
+/// Handles `GameOverEvent` to announce the result
+pub fn handle_game_over_events(mut game_over_reader: EventReader<GameOverEvent>) {
+    for event in game_over_reader.iter() {
+        println!("INFO: game over {}", event.0);
     }
 }
@@ -0,0 +1,67 @@
+use crate::board::events::{LoadGameEvent, ReplayEvent};
+use crate::board::Replay;
+use crate::some_or_return;
+use bevy::prelude::*;
+
+/// Handles `ReplayEvent` to move through the replayed game and shows the reached position
+pub fn handle_replay_events(
+    mut commands: Commands,
+    mut replay: Option<ResMut<Replay>>,
+    mut replay_reader: EventReader<ReplayEvent>,
+    mut load_game_writer: EventWriter<LoadGameEvent>,
+) {
+    for event in replay_reader.iter() {
+        if let ReplayEvent::Load(game) = event {
+            println!(
+                "INFO: replaying {} vs {}",
+                game.tag("White").unwrap_or("?"),
+                game.tag("Black").unwrap_or("?")
+            );
+            // the resource is only inserted at the end of the stage,
+            // so the first position is loaded right away
+            load_game_writer.send(LoadGameEvent::new(game.tree.start.clone()));
+            commands.insert_resource(Replay::new(*game.clone()));
+            continue;
+        }
+
+        // later events may still load a game to replay
+        let replay = match replay.as_mut() {
+            Some(replay) => replay,
+            None => continue,
+        };
+        match event {
+            ReplayEvent::Start => replay.current = 0,
+            ReplayEvent::Back => replay.current = replay.current.saturating_sub(1),
+            ReplayEvent::Forward => replay.current = (replay.current + 1).min(replay.moves.len()),
+            ReplayEvent::End => replay.current = replay.moves.len(),
+            ReplayEvent::Exit => {
+                commands.remove_resource::<Replay>();
+                continue;
+            }
+            ReplayEvent::Load(_) => unreachable!(),
+        }
+        load_game_writer.send(LoadGameEvent {
+            start: replay.game.tree.start.clone(),
+            moves: replay.moves[..replay.current].to_vec(),
+        });
+    }
+}
+
+/// Plays the next move of the replayed game whenever the autoplay timer finishes
+pub fn autoplay_replay(
+    time: Res<Time>,
+    mut replay: Option<ResMut<Replay>>,
+    mut replay_writer: EventWriter<ReplayEvent>,
+) {
+    let replay = some_or_return!(replay.as_mut());
+    if !replay.autoplay {
+        return;
+    }
+    if replay.is_at_end() {
+        replay.autoplay = false;
+        return;
+    }
+    if replay.timer.tick(time.delta()).just_finished() {
+        replay_writer.send(ReplayEvent::Forward);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::board::components::{Piece, PossibleTarget, Selected, Square};
 use crate::board::events::PieceSelectionEvent;
+use crate::board::position::Position;
 use crate::board::{utils, SelectedPiece};
 use crate::constants::{
     PIECE_Z_AXIS, POSSIBLE_TARGET_FILL_COLOR, POSSIBLE_TARGET_OUTLINE_COLOR,
@@ -44,9 +45,9 @@ pub fn handle_piece_selection_events(
     selected_squares_q: Query<Entity, (With<Square>, With<Selected>)>,
     possible_targets_q: Query<Entity, With<PossibleTarget>>,
     squares_q: Query<(Entity, &Location), With<Square>>,
-    piece_locations_q: Query<(&Piece, &Location)>,
     piece_q: Query<&Parent, With<Piece>>,
     selected_piece: Option<Res<SelectedPiece>>,
+    position: Res<Position>,
     mut selection_event_reader: EventReader<PieceSelectionEvent>,
 ) {
     for event in selection_event_reader.iter() {
@@ -61,7 +62,7 @@ pub fn handle_piece_selection_events(
                 });
 
                 let square = ok_or_return!(piece_q.get(*piece)).get();
-                let selected = SelectedPiece::new(square, *piece, world, &piece_locations_q)
+                let selected = SelectedPiece::new(square, *piece, world, &position)
                     .expect("unable to select piece");
 
                 // mark squares and piece
@@ -100,7 +101,7 @@ pub fn handle_piece_selection_events(
                 utils::deselect_piece(&mut commands, selected.piece);
 
                 let square = ok_or_return!(piece_q.get(*piece)).get();
-                let selected = SelectedPiece::new(square, *piece, world, &piece_locations_q)
+                let selected = SelectedPiece::new(square, *piece, world, &position)
                     .expect("unable to select piece");
 
                 // mark squares and piece
@@ -1,15 +1,29 @@
-use crate::board::components::{Board, File, Location, Piece, PieceColor, PieceType, Square};
+use crate::board::components::{Board, File, Location, Piece, Square};
+use crate::board::position::Position;
 use crate::board::utils;
 use crate::constants::{
     BOARD_HEIGHT, BOARD_LEGEND_FONT_SIZE, BOARD_PADDING, BOARD_WIDTH, PIECE_Z_AXIS, SQUARE_Z_AXIS,
+    WINDOW_HEIGHT,
 };
 use crate::resources::{DefaultFont, PieceTheme};
-use crate::{SQUARE_SIZE, WINDOW_HEIGHT};
+use crate::SQUARE_SIZE;
 use bevy::prelude::*;
 use bevy_svg::prelude::*;
 
-/// Sets up the board, all squares and the default position for pieces
-pub fn setup_board(mut commands: Commands, font: Res<DefaultFont>, piece_theme: Res<PieceTheme>) {
+/// Sets up the board, all squares and the pieces of the current `Position`
+pub fn setup_board(
+    mut commands: Commands,
+    font: Res<DefaultFont>,
+    piece_theme: Res<PieceTheme>,
+    position: Res<Position>,
+) {
+    draw_vertical_legend(&mut commands, &font);
+    draw_horizontal_legend(&mut commands, &font);
+    spawn_board(&mut commands, &piece_theme, &position);
+}
+
+/// Spawns the board with all squares and the pieces of the given `Position`
+pub fn spawn_board(commands: &mut Commands, piece_theme: &Res<PieceTheme>, position: &Position) {
     let board_bundle = SpriteBundle {
         sprite: Sprite {
             custom_size: Some(Vec2::new(BOARD_WIDTH, BOARD_HEIGHT)),
@@ -17,12 +31,10 @@ pub fn setup_board(mut commands: Commands, font: Res<DefaultFont>, piece_theme:
         },
         ..default()
     };
-    draw_vertical_legend(&mut commands, &font);
-    draw_horizontal_legend(&mut commands, &font);
     commands
         .spawn_bundle(board_bundle)
         .with_children(|parent| {
-            draw_squares(parent, &piece_theme);
+            draw_squares(parent, piece_theme, position);
         })
         .insert(Board);
 }
@@ -110,7 +122,7 @@ fn draw_vertical_legend(commands: &mut Commands, font: &Res<DefaultFont>) {
 }
 
 /// Draws up all squares as children for the given `board`
-fn draw_squares(board: &mut ChildBuilder, piece_theme: &Res<PieceTheme>) {
+fn draw_squares(board: &mut ChildBuilder, piece_theme: &Res<PieceTheme>, position: &Position) {
     let board_offset = BOARD_WIDTH / 2.0 - utils::center_offset() + BOARD_PADDING;
     // iterate over files
     for x in 0..8 {
@@ -135,40 +147,21 @@ fn draw_squares(board: &mut ChildBuilder, piece_theme: &Res<PieceTheme>) {
                 .insert(Square)
                 .insert(location)
                 .with_children(|parent| {
-                    place_piece(parent, location, piece_theme);
+                    if let Some(piece) = position.piece_at(&location) {
+                        place_piece(parent, piece, location, piece_theme);
+                    }
                 });
         }
     }
 }
 
 /// Places a `Piece` on the given position as a direct child of the given `parent`.
-fn place_piece(square: &mut ChildBuilder, location: Location, piece_theme: &Res<PieceTheme>) {
-    let piece = match location {
-        // first rank
-        Location { x: 0, y: 0 } => Piece::new(PieceType::Rook, PieceColor::White),
-        Location { x: 1, y: 0 } => Piece::new(PieceType::Knight, PieceColor::White),
-        Location { x: 2, y: 0 } => Piece::new(PieceType::Bishop, PieceColor::White),
-        Location { x: 3, y: 0 } => Piece::new(PieceType::Queen, PieceColor::White),
-        Location { x: 4, y: 0 } => Piece::new(PieceType::King, PieceColor::White),
-        Location { x: 5, y: 0 } => Piece::new(PieceType::Bishop, PieceColor::White),
-        Location { x: 6, y: 0 } => Piece::new(PieceType::Knight, PieceColor::White),
-        Location { x: 7, y: 0 } => Piece::new(PieceType::Rook, PieceColor::White),
-        // second rank
-        Location { y: 1, .. } => Piece::new(PieceType::Pawn, PieceColor::White),
-        // seventh rank
-        Location { y: 6, .. } => Piece::new(PieceType::Pawn, PieceColor::Black),
-        // eight rank
-        Location { x: 0, y: 7 } => Piece::new(PieceType::Rook, PieceColor::Black),
-        Location { x: 1, y: 7 } => Piece::new(PieceType::Knight, PieceColor::Black),
-        Location { x: 2, y: 7 } => Piece::new(PieceType::Bishop, PieceColor::Black),
-        Location { x: 3, y: 7 } => Piece::new(PieceType::Queen, PieceColor::Black),
-        Location { x: 4, y: 7 } => Piece::new(PieceType::King, PieceColor::Black),
-        Location { x: 5, y: 7 } => Piece::new(PieceType::Bishop, PieceColor::Black),
-        Location { x: 6, y: 7 } => Piece::new(PieceType::Knight, PieceColor::Black),
-        Location { x: 7, y: 7 } => Piece::new(PieceType::Rook, PieceColor::Black),
-        Location { .. } => return,
-    };
-
+fn place_piece(
+    square: &mut ChildBuilder,
+    piece: Piece,
+    location: Location,
+    piece_theme: &Res<PieceTheme>,
+) {
     let svg = piece_theme
         .vectors
         .get(&piece.resource_name())
@@ -177,7 +170,7 @@ fn place_piece(square: &mut ChildBuilder, location: Location, piece_theme: &Res<
 
     let center_offset = utils::center_offset();
     let transform = Transform {
-        translation: Vec3::new(center_offset * -1.0, center_offset, PIECE_Z_AXIS),
+        translation: Vec3::new(-center_offset, center_offset, PIECE_Z_AXIS),
         scale: Vec3::new(1.6, 1.6, 0.0),
         ..default()
     };
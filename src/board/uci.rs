@@ -0,0 +1,132 @@
+use crate::board::components::{Location, PieceType};
+use crate::board::position::{Move, Position};
+use std::fmt;
+
+/// Describes why a move in UCI notation could not be turned into a move
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UciError {
+    /// The string is not a move in coordinate notation like `e2e4` or `e7e8q`
+    Malformed,
+    /// The move is not legal in the position
+    Illegal,
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            UciError::Malformed => "malformed move",
+            UciError::Illegal => "illegal move",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for UciError {}
+
+impl Move {
+    /// Parses a move in the long algebraic notation of the UCI protocol,
+    /// castling is given by the king move like `e1g1` and promotions by a lowercase suffix like `e7e8q`
+    pub fn from_uci(uci: &str) -> Result<Self, UciError> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(UciError::Malformed);
+        }
+        let from = Location::from_notation(&uci[0..2]).ok_or(UciError::Malformed)?;
+        let to = Location::from_notation(&uci[2..4]).ok_or(UciError::Malformed)?;
+        let promotion = match uci[4..].chars().next() {
+            Some(letter) if letter.is_ascii_lowercase() => {
+                let kind = PieceType::from_notation(letter.to_ascii_uppercase())
+                    .filter(|kind| PieceType::PROMOTIONS.contains(kind))
+                    .ok_or(UciError::Malformed)?;
+                Some(kind)
+            }
+            Some(_) => return Err(UciError::Malformed),
+            None => None,
+        };
+        Ok(Self::new(from, to, promotion))
+    }
+
+    /// Returns the move in the long algebraic notation of the UCI protocol
+    pub fn to_uci(self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(kind) = self.promotion {
+            write!(f, "{}", kind.notation().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl Position {
+    /// Parses a move in UCI notation and checks that it is legal in this position
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, UciError> {
+        let mv = Move::from_uci(uci)?;
+        match self.legal_moves().contains(&mv) {
+            true => Ok(mv),
+            false => Err(UciError::Illegal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::components::{Location, PieceType};
+    use crate::board::position::{Move, Position};
+    use crate::board::uci::UciError;
+
+    #[test]
+    fn formats_moves() {
+        let mv = Move::new(Location::new(4, 1), Location::new(4, 3), None);
+        assert_eq!(mv.to_uci(), "e2e4");
+        let mv = Move::new(
+            Location::new(4, 6),
+            Location::new(3, 7),
+            Some(PieceType::Knight),
+        );
+        assert_eq!(mv.to_uci(), "e7d8n");
+    }
+
+    #[test]
+    fn round_trips_legal_moves() {
+        let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1R1K b kq - 0 1";
+        let position = Position::from_fen(fen).unwrap();
+        for mv in position.legal_moves() {
+            assert_eq!(position.parse_uci(&mv.to_uci()), Ok(mv));
+        }
+        assert_eq!(
+            position.parse_uci("b2a1r"),
+            Ok(Move::new(
+                Location::new(1, 1),
+                Location::new(0, 0),
+                Some(PieceType::Rook)
+            ))
+        );
+    }
+
+    #[test]
+    fn castling_is_the_king_move() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let position = Position::from_fen(fen).unwrap();
+        assert_eq!(
+            position.parse_uci("e1g1"),
+            Ok(Move::new(Location::new(4, 0), Location::new(6, 0), None))
+        );
+        assert_eq!(position.san(&position.parse_uci("e1c1").unwrap()), "O-O-O");
+    }
+
+    #[test]
+    fn reports_invalid_moves() {
+        for uci in [
+            "", "e2", "e2e", "e2e9", "i2e4", "e7e8k", "e7e8Q", "e7e8qq", "e2-e4",
+        ] {
+            assert_eq!(Move::from_uci(uci), Err(UciError::Malformed), "{}", uci);
+        }
+        let position = Position::default();
+        assert_eq!(position.parse_uci("e2e5"), Err(UciError::Illegal));
+        assert_eq!(position.parse_uci("e1g1"), Err(UciError::Illegal));
+    }
+}
@@ -1,8 +1,10 @@
-use crate::board::components::{PieceColor, Selected, SquareColor};
-use crate::board::events::CheckedPieceMoveEvent;
-use crate::board::SelectedPiece;
+use crate::board::components::{Selected, Square, SquareColor};
+use crate::board::{PlayedMove, SelectedPiece};
+use crate::resources::PieceTheme;
 use crate::{BoardCamera, Location, Piece, SQUARE_SIZE};
 use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use bevy_svg::prelude::*;
 
 /// Translates the current cursor position to world coordinates
 pub fn translate_cursor_pos(
@@ -46,7 +48,7 @@ pub fn rank_for_index(index: usize) -> usize {
 
 /// Returns the color for the given indicates
 pub fn square_color(x: usize, y: usize) -> SquareColor {
-    if (x + y + 1) % 2 == 0 {
+    if (x + y + 1).is_multiple_of(2) {
         SquareColor::Light
     } else {
         SquareColor::Dark
@@ -64,29 +66,102 @@ pub fn deselect_piece(commands: &mut Commands, piece: Entity) {
     commands.remove_resource::<SelectedPiece>();
 }
 
+/// Mirrors the given move onto the board entities by moving the piece entity,
+/// despawning a captured piece, moving the castling rook and swapping the graphic of a promoted pawn
+pub fn mirror_move(
+    commands: &mut Commands,
+    played_move: &PlayedMove,
+    squares_q: &Query<(Entity, &Location, Option<&Children>), With<Square>>,
+    pieces_q: &Query<&Piece>,
+    piece_theme: &PieceTheme,
+    svgs: &Assets<Svg>,
+) {
+    let (source_square, piece) = resolve_square(&played_move.source, squares_q, pieces_q).unwrap();
+    let (piece, mut piece_comp) = piece.expect("moved piece must exist");
+    let (target_square, _) = resolve_square(&played_move.target, squares_q, pieces_q).unwrap();
+
+    // remove the captured piece, which is not on the target square when captured en passant
+    if let Some(captured_location) = played_move.captured_location() {
+        if let Some((_, Some((captured, _)))) =
+            resolve_square(&captured_location, squares_q, pieces_q)
+        {
+            commands.entity(captured).despawn_recursive();
+        }
+    }
+
+    if let Some(kind) = played_move.promotion {
+        piece_comp.kind = kind;
+        swap_piece_svg(commands, piece, &piece_comp, piece_theme, svgs);
+    }
+    relocate_piece(
+        commands,
+        piece,
+        piece_comp,
+        source_square,
+        target_square,
+        played_move.target,
+    );
+
+    if let Some(side) = played_move.castling {
+        let rank = played_move.source.y;
+        let rook_source = Location::new(side.rook_source_file(), rank);
+        let rook_target = Location::new(side.rook_target_file(), rank);
+        let (rook_square, rook) = resolve_square(&rook_source, squares_q, pieces_q).unwrap();
+        let (rook, rook_comp) = rook.expect("castling requires a rook");
+        let (rook_target_square, _) = resolve_square(&rook_target, squares_q, pieces_q).unwrap();
+        relocate_piece(
+            commands,
+            rook,
+            rook_comp,
+            rook_square,
+            rook_target_square,
+            rook_target,
+        );
+    }
+}
+
+/// Replaces the vector graphic of the given piece entity to match the given `Piece`
+pub fn swap_piece_svg(
+    commands: &mut Commands,
+    entity: Entity,
+    piece: &Piece,
+    piece_theme: &PieceTheme,
+    svgs: &Assets<Svg>,
+) {
+    let svg = piece_theme
+        .vectors
+        .get(&piece.resource_name())
+        .cloned()
+        .unwrap();
+    // the mesh is only linked once the asset has been loaded, so it has to be replaced as well
+    if let Some(mesh) = svgs.get(&svg).map(|svg| svg.mesh.clone()) {
+        commands.entity(entity).insert(Mesh2dHandle(mesh));
+    }
+    commands.entity(entity).insert(svg);
+}
+
 /// Removes piece entity from source square and adds it to the target square as a child,
 /// also replaces `Location` component on entity and sets `has_moved` to reflect this move properly
-pub fn update_entity_for_move(
+pub fn relocate_piece(
     commands: &mut Commands,
-    event: &CheckedPieceMoveEvent,
+    piece: Entity,
+    mut piece_comp: Piece,
+    source: Entity,
     target: Entity,
     loc_comp: Location,
 ) {
     // Update square children
-    commands
-        .entity(event.selected.square)
-        .remove_children(&[event.selected.piece]);
-    commands.entity(target).add_child(event.selected.piece);
+    commands.entity(source).remove_children(&[piece]);
+    commands.entity(target).add_child(piece);
 
     // Update `Location` component for piece entity
-    commands.entity(event.selected.piece).remove::<Location>();
-    commands.entity(event.selected.piece).insert(loc_comp);
+    commands.entity(piece).remove::<Location>();
+    commands.entity(piece).insert(loc_comp);
 
     // Update `Piece` component for piece entity
-    let mut piece_comp = event.selected.piece_comp;
     piece_comp.has_moved = true;
-    commands.entity(event.selected.piece).remove::<Piece>();
-    commands.entity(event.selected.piece).insert(piece_comp);
+    commands.entity(piece).remove::<Piece>();
+    commands.entity(piece).insert(piece_comp);
 }
 
 /// Adjusts the given piece `GlobalTransform` to square `GlobalTransform`
@@ -108,39 +183,13 @@ pub fn resolve_piece(entities: &[Entity], pieces_q: &Query<&Piece>) -> Option<(E
     None
 }
 
-/// Returns a new vector of `Location` created by the given location and offsets
-pub fn translate_from_offsets(location: &Location, offsets: Vec<Vec<isize>>) -> Vec<Location> {
-    offsets
-        .into_iter()
-        .filter_map(|off| location.translate(off[0], off[1]))
-        .collect()
-}
-
-/// Returns a tuple of `Vec<Location>`, the first tuple element contains
-/// same color pieces and the second all different color pieces
-pub fn grouped_piece_locations(
-    selected_piece_color: &PieceColor,
-    pieces: &Query<(&Piece, &Location)>,
-) -> (Vec<Location>, Vec<Location>) {
-    let same_color_pieces = pieces
-        .iter()
-        .filter_map(
-            |(piece, location)| match selected_piece_color == &piece.color {
-                true => Some(location),
-                false => None,
-            },
-        )
-        .cloned()
-        .collect();
-    let different_color_pieces = pieces
-        .iter()
-        .filter_map(
-            |(piece, location)| match selected_piece_color != &piece.color {
-                true => Some(location),
-                false => None,
-            },
-        )
-        .cloned()
-        .collect();
-    (same_color_pieces, different_color_pieces)
+/// Returns the square entity for the given location and the piece placed on it, if any
+pub fn resolve_square(
+    location: &Location,
+    squares_q: &Query<(Entity, &Location, Option<&Children>), With<Square>>,
+    pieces_q: &Query<&Piece>,
+) -> Option<(Entity, Option<(Entity, Piece)>)> {
+    let (square, _, children) = squares_q.iter().find(|(_, loc, _)| *loc == location)?;
+    let piece = children.and_then(|children| resolve_piece(children, pieces_q));
+    Some((square, piece))
 }
@@ -0,0 +1,166 @@
+use crate::board::components::{Location, Piece, PieceColor};
+use crate::board::position::CastlingRights;
+
+/// Random keys that are combined with xor to a 64-bit hash of a position
+struct Keys {
+    /// Indexed by `PieceColor::index`, `PieceType::index` and `Location::index`
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    /// White kingside, white queenside, black kingside and black queenside
+    castling: [u64; 4],
+    /// Indexed by the file of the en passant square
+    en_passant: [u64; 8],
+}
+
+/// The keys are generated at compile time with a fixed seed, so they are the same on every run
+const KEYS: Keys = Keys::generate(0x5eed_c0de_1234_5678);
+
+impl Keys {
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = Keys {
+            pieces: [[[0; 64]; 6]; 2],
+            black_to_move: 0,
+            castling: [0; 4],
+            en_passant: [0; 8],
+        };
+        let mut color = 0;
+        while color < 2 {
+            let mut kind = 0;
+            while kind < 6 {
+                let mut index = 0;
+                while index < 64 {
+                    state = next(state);
+                    keys.pieces[color][kind][index] = scramble(state);
+                    index += 1;
+                }
+                kind += 1;
+            }
+            color += 1;
+        }
+        state = next(state);
+        keys.black_to_move = scramble(state);
+        let mut i = 0;
+        while i < 4 {
+            state = next(state);
+            keys.castling[i] = scramble(state);
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 8 {
+            state = next(state);
+            keys.en_passant[i] = scramble(state);
+            i += 1;
+        }
+        keys
+    }
+}
+
+/// Advances the xorshift state
+const fn next(mut state: u64) -> u64 {
+    state ^= state >> 12;
+    state ^= state << 25;
+    state ^= state >> 27;
+    state
+}
+
+/// Turns a xorshift state into a well distributed key
+const fn scramble(state: u64) -> u64 {
+    state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
+/// Returns the key for the given piece placed on the given location
+pub fn piece(piece: &Piece, location: &Location) -> u64 {
+    KEYS.pieces[piece.color.index()][piece.kind.index()][location.index()]
+}
+
+/// Returns the key for the given side to move, white to move does not change the hash
+pub fn side_to_move(color: &PieceColor) -> u64 {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => KEYS.black_to_move,
+    }
+}
+
+/// Returns the combined key of all castling rights that are still available
+pub fn castling(rights: &CastlingRights) -> u64 {
+    [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ]
+    .into_iter()
+    .zip(KEYS.castling)
+    .filter(|(right, _)| *right)
+    .fold(0, |key, (_, right_key)| key ^ right_key)
+}
+
+/// Returns the key for an en passant square on the given file
+pub fn en_passant(file: usize) -> u64 {
+    KEYS.en_passant[file]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::components::Location;
+    use crate::board::position::{Move, Position};
+
+    fn play(position: &mut Position, moves: &[((usize, usize), (usize, usize))]) {
+        for (from, to) in moves {
+            let mv = Move::new(
+                Location::new(from.0, from.1),
+                Location::new(to.0, to.1),
+                None,
+            );
+            position.make_move(mv);
+        }
+    }
+
+    #[test]
+    fn transpositions_have_the_same_key() {
+        // 1. Nf3 Nf6 2. Nc3 and 1. Nc3 Nf6 2. Nf3
+        let mut first = Position::default();
+        play(
+            &mut first,
+            &[((6, 0), (5, 2)), ((6, 7), (5, 5)), ((1, 0), (2, 2))],
+        );
+        let mut second = Position::default();
+        play(
+            &mut second,
+            &[((1, 0), (2, 2)), ((6, 7), (5, 5)), ((6, 0), (5, 2))],
+        );
+        assert_eq!(first.zobrist_key(), second.zobrist_key());
+        assert_ne!(first.zobrist_key(), Position::default().zobrist_key());
+    }
+
+    #[test]
+    fn incremental_key_matches_fen() {
+        // 1. e4 d5 2. e5 f5 allows exf6 en passant
+        let mut position = Position::default();
+        play(
+            &mut position,
+            &[
+                ((4, 1), (4, 3)),
+                ((3, 6), (3, 4)),
+                ((4, 3), (4, 4)),
+                ((5, 6), (5, 4)),
+            ],
+        );
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        assert_eq!(
+            position.zobrist_key(),
+            Position::from_fen(fen).unwrap().zobrist_key()
+        );
+    }
+
+    #[test]
+    fn en_passant_only_counts_if_capturable() {
+        let with = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let without = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(
+            Position::from_fen(with).unwrap().zobrist_key(),
+            Position::from_fen(without).unwrap().zobrist_key()
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use crate::board::components::{Location, PieceColor};
+use crate::board::diagram::{self, DiagramOptions};
+use crate::board::epd::{self, EngineAnswer, EpdRecord};
+use crate::board::fen::INITIAL_FEN;
+use crate::board::perft::{divide, perft};
+use crate::board::pgn_reader::{PgnReadError, PgnReader};
+use crate::board::position::{Move, Position};
+use crate::board::search::{self, SearchLimits};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: chess-engine <perft|divide> <depth> [fen] [moves <uci>...]
+       chess-engine search <depth|movetime> <limit> [fen] [moves <uci>...]
+       chess-engine epd <file> <depth|movetime> <limit> <builtin|uci engine command...>
+       chess-engine pgn <file>
+       chess-engine diagram <output.svg> [fen] [flip] [coordinates] [last <uci>] [arrow <uci>...]";
+
+/// Runs the command given on the command line and returns false if there is none,
+/// in which case the GUI should be started
+pub fn run(args: &[String]) -> bool {
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => return false,
+    };
+    let result = match command {
+        "perft" | "divide" => run_perft(command == "divide", &args[1..]),
+        "search" => run_search(&args[1..]),
+        "epd" => run_epd(&args[1..]),
+        "pgn" => run_pgn(&args[1..]),
+        "diagram" => run_diagram(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("ERROR: {}", err);
+        std::process::exit(1);
+    }
+    true
+}
+
+/// Prints the perft node count of a position, for `divide` the node count below every move as well.
+/// The output format matches common reference engines to make it easy to compare,
+/// moves given in UCI notation are played first to narrow down a wrong count.
+fn run_perft(divide_moves: bool, args: &[String]) -> Result<(), String> {
+    let depth = match args.first().map(|depth| depth.parse::<usize>()) {
+        Some(Ok(depth)) => depth,
+        _ => return Err(USAGE.to_string()),
+    };
+    let position = parse_position(&args[1..])?;
+
+    let start = Instant::now();
+    let nodes = match divide_moves {
+        true => {
+            let moves = divide(&position, depth);
+            for (mv, nodes) in &moves {
+                println!("{}: {}", mv.to_uci(), nodes);
+            }
+            println!();
+            moves.iter().map(|(_, nodes)| nodes).sum()
+        }
+        false => perft(&position, depth),
+    };
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// Searches the best move of a position with the built-in engine
+/// and prints every iteration like the `info` output of UCI engines
+fn run_search(args: &[String]) -> Result<(), String> {
+    let limits = match args {
+        [kind, limit, ..] => parse_search_limits(kind, limit)?,
+        _ => return Err(USAGE.to_string()),
+    };
+    let position = parse_position(&args[2..])?;
+
+    let start = Instant::now();
+    let result = search::search(&position, &[], &limits, &AtomicBool::new(false), |result| {
+        println!("info {}", result)
+    });
+    match result.best_move {
+        Some(mv) => println!("bestmove {}", mv),
+        None => println!("bestmove (none)"),
+    }
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// Parses a position given as FEN, which is the initial position if it is omitted,
+/// followed by moves in UCI notation after `moves` that are played on it
+fn parse_position(args: &[String]) -> Result<Position, String> {
+    let (fen_args, moves) = match args.iter().position(|arg| arg == "moves") {
+        Some(index) => (&args[..index], &args[index + 1..]),
+        None => (args, &args[args.len()..]),
+    };
+    let fen = match fen_args.is_empty() {
+        true => INITIAL_FEN.to_string(),
+        false => fen_args.join(" "),
+    };
+    let mut position = Position::from_fen(&fen).map_err(|err| format!("invalid FEN: {}", err))?;
+    for uci in moves {
+        let mv = position
+            .parse_uci(uci)
+            .map_err(|err| format!("{} '{}'", err, uci))?;
+        position.make_move(mv);
+    }
+    Ok(position)
+}
+
+/// Parses a search depth in plies or a time in milliseconds
+fn parse_search_limits(kind: &str, limit: &str) -> Result<SearchLimits, String> {
+    let limit = limit.parse::<u64>().map_err(|_| USAGE.to_string())?;
+    match kind {
+        "depth" => Ok(SearchLimits::depth(limit as usize)),
+        "movetime" => Ok(SearchLimits::time(Duration::from_millis(limit))),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Runs an engine speaking the UCI protocol on every best move or avoid move record of an EPD file
+/// with a search depth or a time in milliseconds per position, and prints how many it solves.
+/// The engine `builtin` runs the search of this program instead.
+fn run_epd(args: &[String]) -> Result<(), String> {
+    let (path, limits, limit, engine_command) = match args {
+        [path, kind, limit, engine @ ..] if !engine.is_empty() => (
+            path,
+            parse_search_limits(kind, limit)?,
+            format!("{} {}", kind, limit),
+            engine,
+        ),
+        _ => return Err(USAGE.to_string()),
+    };
+    let text =
+        std::fs::read_to_string(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+    let records =
+        epd::read_epd(&text).map_err(|(line, err)| format!("{}:{}: {}", path, line, err))?;
+    let mut engine = match engine_command {
+        [builtin] if builtin == "builtin" => None,
+        command => Some(UciEngine::start(command)?),
+    };
+
+    let start = Instant::now();
+    let mut engine_error = None;
+    let report = epd::run_suite(&records, |record| {
+        let engine = match engine.as_mut() {
+            Some(engine) => engine,
+            None => {
+                let stop = AtomicBool::new(false);
+                return EngineAnswer::from(&search::search(
+                    &record.position,
+                    &[],
+                    &limits,
+                    &stop,
+                    |_| {},
+                ));
+            }
+        };
+        if engine_error.is_some() {
+            return EngineAnswer::default();
+        }
+        engine.search(record, &limit).unwrap_or_else(|err| {
+            engine_error = Some(err);
+            EngineAnswer::default()
+        })
+    });
+    if let Some(err) = engine_error {
+        return Err(err);
+    }
+    for (index, answer) in &report.failed {
+        let record = &records[*index];
+        let played = answer
+            .best_move
+            .map(Move::to_uci)
+            .unwrap_or_else(|| "none".to_string());
+        let id = record
+            .id()
+            .map(String::from)
+            .unwrap_or_else(|| format!("#{}", index + 1));
+        match record.comment() {
+            Some(comment) => println!("failed {}: {} ({})", id, played, comment),
+            None => println!("failed {}: {}", id, played),
+        }
+    }
+    println!();
+    println!("Solved: {}/{}", report.solved, report.total);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+/// Lists the players and results of all games in a PGN database and validates their moves,
+/// malformed games are reported with their byte offset and skipped
+fn run_pgn(args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    let file = File::open(path).map_err(|err| format!("unable to read {}: {}", path, err))?;
+    let mut games = 0;
+    let mut malformed = 0;
+    for game in PgnReader::new(BufReader::new(file)) {
+        games += 1;
+        let game = game.and_then(|raw| {
+            raw.parse()
+                .map(|_| raw.clone())
+                .map_err(|error| PgnReadError::Malformed {
+                    offset: raw.offset,
+                    error,
+                })
+        });
+        match game {
+            Ok(raw) => println!(
+                "{}: {} - {} {}",
+                games,
+                raw.tag("White").unwrap_or("?"),
+                raw.tag("Black").unwrap_or("?"),
+                raw.tag("Result").unwrap_or("*")
+            ),
+            Err(PgnReadError::Io(err)) => return Err(format!("unable to read {}: {}", path, err)),
+            Err(err) => {
+                malformed += 1;
+                println!("{}: {}", games, err);
+            }
+        }
+    }
+    println!();
+    println!("Games: {}, malformed: {}", games, malformed);
+    Ok(())
+}
+
+/// Writes an SVG diagram of a position, which is the initial position if no FEN is given.
+/// Squares for the last move highlight and arrows are given like UCI moves, e.g. `e2e4`.
+fn run_diagram(args: &[String]) -> Result<(), String> {
+    let (output, args) = match args.split_first() {
+        Some((output, args)) => (output, args),
+        None => return Err(USAGE.to_string()),
+    };
+    let squares = |uci: Option<&String>| match uci.map(|uci| uci.split_at_checked(2)) {
+        Some(Some((from, to))) => {
+            match (Location::from_notation(from), Location::from_notation(to)) {
+                (Some(from), Some(to)) => Ok((from, to)),
+                _ => Err(format!("malformed squares '{}'", uci.unwrap())),
+            }
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    let mut options = DiagramOptions::default();
+    let mut fen_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "flip" => options.orientation = PieceColor::Black,
+            "coordinates" => options.coordinates = true,
+            "last" => options.last_move = Some(squares(args.next())?),
+            "arrow" => options.arrows.push(squares(args.next())?),
+            _ => fen_args.push(arg.as_str()),
+        }
+    }
+    let fen = match fen_args.is_empty() {
+        true => INITIAL_FEN.to_string(),
+        false => fen_args.join(" "),
+    };
+    let position = Position::from_fen(&fen).map_err(|err| format!("invalid FEN: {}", err))?;
+
+    let svg = diagram::render_svg(&position, &options, Path::new("assets"))
+        .map_err(|err| format!("unable to render diagram: {}", err))?;
+    std::fs::write(output, svg).map_err(|err| format!("unable to write {}: {}", output, err))?;
+    println!("INFO: saved diagram to {}", output);
+    Ok(())
+}
+
+/// An external engine process that is controlled with the UCI protocol over its stdin and stdout
+struct UciEngine {
+    process: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl UciEngine {
+    fn start(command: &[String]) -> Result<Self, String> {
+        let mut process = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("unable to start {}: {}", command[0], err))?;
+        let stdin = process.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(process.stdout.take().expect("stdout is piped")).lines();
+        let mut engine = Self {
+            process,
+            stdin,
+            stdout,
+        };
+        engine.send("uci")?;
+        engine.wait_for("uciok")?;
+        Ok(engine)
+    }
+
+    /// Searches the position of the record with the given `go` limit and returns the chosen move,
+    /// which is `None` if the engine reports no move or an illegal one,
+    /// together with the last mate score the engine has reported
+    fn search(&mut self, record: &EpdRecord, limit: &str) -> Result<EngineAnswer, String> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.wait_for("readyok")?;
+        self.send(&format!("position fen {}", record.position.to_fen()))?;
+        self.send(&format!("go {}", limit))?;
+        let mut answer = EngineAnswer::default();
+        loop {
+            let line = self.read_line()?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["bestmove", uci, ..] => {
+                    answer.best_move = record.position.parse_uci(uci).ok();
+                    return Ok(answer);
+                }
+                ["info", info @ ..] => {
+                    // every score replaces the one of the previous line, so a mate found at
+                    // a lower depth does not stick once a deeper search gives a `cp` score
+                    if let Some(index) = info.iter().position(|token| *token == "score") {
+                        answer.mate = match info.get(index + 1) {
+                            // negative scores are mates against the side to move
+                            Some(&"mate") => {
+                                info.get(index + 2).and_then(|moves| moves.parse().ok())
+                            }
+                            _ => None,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command).map_err(|err| format!("engine closed stdin: {}", err))
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        match self.stdout.next() {
+            Some(line) => line.map_err(|err| format!("unable to read from engine: {}", err)),
+            None => Err("engine exited unexpectedly".to_string()),
+        }
+    }
+
+    /// Reads the output of the engine until a line starts with the given token
+    fn wait_for(&mut self, token: &str) -> Result<(), String> {
+        while self.read_line()?.split_whitespace().next() != Some(token) {}
+        Ok(())
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.process.wait();
+    }
+}
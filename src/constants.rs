@@ -28,3 +28,8 @@ pub const POSSIBLE_TARGET_FILL_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.1);
 pub const POSSIBLE_TARGET_OUTLINE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.9);
 pub const POSSIBLE_TARGET_RADIUS: RegularPolygonFeature = RegularPolygonFeature::Radius(15.0);
 pub const POSSIBLE_TARGET_OUTLINE_WIDTH: f32 = 2.5;
+
+pub const REPLAY_MOVE_SECS: f32 = 1.0;
+pub const ENGINE_SEARCH_SECS: f32 = 3.0;
+
+pub const DIAGRAM_ARROW_COLOR: Color = Color::rgba(0.08, 0.47, 0.11, 0.8);
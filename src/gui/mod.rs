@@ -9,3 +9,24 @@ pub struct OccupiedScreenSpace {
     pub right: f32,
     pub bottom: f32,
 }
+
+/// Holds the text of the FEN input field and the error of the last attempt to load it
+#[derive(Default, Debug)]
+pub struct FenInput {
+    pub text: String,
+    pub error: Option<String>,
+}
+
+/// Holds the text of the move input field and the error of the last attempt to play it
+#[derive(Default, Debug)]
+pub struct MoveInput {
+    pub text: String,
+    pub error: Option<String>,
+}
+
+/// Holds the path of the PGN file to import and the error of the last attempt to read it
+#[derive(Default, Debug)]
+pub struct PgnInput {
+    pub path: String,
+    pub error: Option<String>,
+}
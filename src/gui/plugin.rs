@@ -1,11 +1,16 @@
-use crate::gui::systems::{render_ui, update_camera_transform_system};
+use crate::gui::systems::{render_promotion_chooser, render_ui, update_camera_transform_system};
+use crate::gui::{FenInput, MoveInput, PgnInput};
 use bevy::prelude::*;
 
 pub struct GuiPlugin;
 
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(render_ui)
+        app.init_resource::<FenInput>()
+            .init_resource::<MoveInput>()
+            .init_resource::<PgnInput>()
+            .add_system(render_ui)
+            .add_system(render_promotion_chooser.after(render_ui))
             .add_system(update_camera_transform_system);
     }
 }
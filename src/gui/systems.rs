@@ -1,7 +1,16 @@
-use crate::board::PlayedMoves;
+use crate::board::components::{PieceType, Square};
+use crate::board::events::{
+    CheckedPieceMoveEvent, ClaimDrawEvent, EngineEvent, LoadGameEvent, MoveTarget, PlayMoveEvent,
+    ReplayEvent, UncheckedPieceMoveEvent,
+};
+use crate::board::position::Position;
+use crate::board::rules::ClaimableDraw;
+use crate::board::{
+    CapturedPieces, EngineSearch, GameResult, PendingPromotion, PlayedMoves, Replay,
+};
 use crate::constants::SIDE_PANEL_RIGHT_WIDTH;
-use crate::gui::{utils, OccupiedScreenSpace};
-use crate::{BoardCamera, OriginalCameraTransforms};
+use crate::gui::{utils, FenInput, MoveInput, OccupiedScreenSpace, PgnInput};
+use crate::{ok_or_return, some_or_return, BoardCamera, OriginalCameraTransforms, SQUARE_SIZE};
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 
@@ -9,6 +18,23 @@ pub fn render_ui(
     mut egui_context: ResMut<EguiContext>,
     mut occupied_screen_space: ResMut<OccupiedScreenSpace>,
     played_moves: Res<PlayedMoves>,
+    captured_pieces: Res<CapturedPieces>,
+    game_result: Option<Res<GameResult>>,
+    claimable_draw: Option<Res<ClaimableDraw>>,
+    position: Res<Position>,
+    replay: Option<ResMut<Replay>>,
+    engine_search: Option<Res<EngineSearch>>,
+    // the text inputs are grouped as a system takes at most 16 parameters
+    (mut move_input, mut fen_input, mut pgn_input): (
+        ResMut<MoveInput>,
+        ResMut<FenInput>,
+        ResMut<PgnInput>,
+    ),
+    mut claim_draw_writer: EventWriter<ClaimDrawEvent>,
+    mut load_game_writer: EventWriter<LoadGameEvent>,
+    mut play_move_writer: EventWriter<PlayMoveEvent>,
+    mut replay_writer: EventWriter<ReplayEvent>,
+    mut engine_writer: EventWriter<EngineEvent>,
 ) {
     occupied_screen_space.left = 0.0;
     occupied_screen_space.top = 0.0;
@@ -16,7 +42,51 @@ pub fn render_ui(
         .default_width(SIDE_PANEL_RIGHT_WIDTH)
         .resizable(false)
         .show(egui_context.ctx_mut(), |ui| {
-            utils::build_played_moves_grid(ui, &played_moves.0);
+            match &replay {
+                Some(replay) => utils::build_game_tree(ui, &replay.game.tree, replay.current),
+                None => utils::build_played_moves_grid(ui, &played_moves),
+            }
+            if let Some(result) = &game_result {
+                utils::build_game_result_label(ui, result);
+            }
+            if let Some(claimable) = claimable_draw {
+                ui.separator();
+                if ui.button(format!("Claim draw ({})", claimable.0)).clicked() {
+                    claim_draw_writer.send(ClaimDrawEvent);
+                }
+            }
+            utils::build_pgn_export_button(
+                ui,
+                &played_moves,
+                game_result.as_deref(),
+                replay.as_deref(),
+            );
+            utils::build_diagram_export_button(ui, &position, &played_moves);
+            if let Some(mut replay) = replay {
+                if let Some(event) = utils::build_replay_controls(ui, &mut replay) {
+                    replay_writer.send(event);
+                }
+            } else if game_result.is_none() {
+                if let Some(event) = utils::build_engine_controls(ui, engine_search.is_some()) {
+                    engine_writer.send(event);
+                }
+                // the engine plays the next move while it is searching
+                let entered_move = match engine_search {
+                    Some(_) => None,
+                    None => utils::build_move_input(ui, &position, &mut move_input),
+                };
+                if let Some(mv) = entered_move {
+                    play_move_writer.send(PlayMoveEvent(mv));
+                }
+            }
+            if let Some(game) = utils::build_pgn_import_controls(ui, &mut pgn_input) {
+                replay_writer.send(ReplayEvent::Load(Box::new(game)));
+            }
+            if let Some(position) = utils::build_fen_controls(ui, &position, &mut fen_input) {
+                // a new game ends the replay of an imported one
+                replay_writer.send(ReplayEvent::Exit);
+                load_game_writer.send(LoadGameEvent::new(position));
+            }
             ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
         })
         .response
@@ -25,6 +95,7 @@ pub fn render_ui(
     occupied_screen_space.bottom = egui::TopBottomPanel::bottom("bottom_panel")
         .resizable(false)
         .show(egui_context.ctx_mut(), |ui| {
+            utils::build_captured_pieces_list(ui, &captured_pieces);
             ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
         })
         .response
@@ -32,6 +103,58 @@ pub fn render_ui(
         .height();
 }
 
+/// Shows a piece chooser on top of the promotion square while a promotion is pending
+pub fn render_promotion_chooser(
+    mut commands: Commands,
+    mut egui_context: ResMut<EguiContext>,
+    pending_promotion: Option<Res<PendingPromotion>>,
+    squares_q: Query<&GlobalTransform, With<Square>>,
+    cameras_q: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    windows: Res<Windows>,
+    mut unchecked_moves_writer: EventWriter<UncheckedPieceMoveEvent>,
+    mut checked_moves_writer: EventWriter<CheckedPieceMoveEvent>,
+) {
+    let pending = some_or_return!(pending_promotion);
+    let square_tf = ok_or_return!(squares_q.get(pending.target));
+    let (camera, camera_tf) = cameras_q.single();
+    let window = some_or_return!(windows.get_primary());
+
+    // anchor the chooser at the upper left corner of the promotion square,
+    // the viewport origin is at the bottom left while egui starts at the top left
+    let corner = square_tf.translation() + Vec3::new(-SQUARE_SIZE / 2.0, SQUARE_SIZE / 2.0, 0.0);
+    let viewport_pos = some_or_return!(camera.world_to_viewport(camera_tf, corner));
+    let pos = egui::pos2(viewport_pos.x, window.height() - viewport_pos.y);
+
+    egui::Area::new("promotion_chooser")
+        .fixed_pos(pos)
+        .order(egui::Order::Foreground)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_width(SQUARE_SIZE);
+                ui.vertical_centered_justified(|ui| {
+                    for kind in PieceType::PROMOTIONS {
+                        if ui.button(kind.to_string()).clicked() {
+                            unchecked_moves_writer.send(UncheckedPieceMoveEvent::promote(
+                                pending.selected.clone(),
+                                pending.target,
+                                kind,
+                            ));
+                            commands.remove_resource::<PendingPromotion>();
+                        }
+                    }
+                    // moves the pawn back to its square
+                    if ui.button("Cancel").clicked() {
+                        checked_moves_writer.send(CheckedPieceMoveEvent::new(
+                            pending.selected.clone(),
+                            MoveTarget::Illegal,
+                        ));
+                        commands.remove_resource::<PendingPromotion>();
+                    }
+                });
+            });
+        });
+}
+
 pub fn update_camera_transform_system(
     occupied_screen_space: Res<OccupiedScreenSpace>,
     original_camera_transform: Res<OriginalCameraTransforms>,
@@ -1,24 +1,333 @@
-use crate::board::PlayedMove;
-use bevy_egui::egui::{Grid, RichText, Ui};
+use crate::board::components::PieceColor;
+use crate::board::diagram::{self, DiagramOptions};
+use crate::board::events::{EngineEvent, ReplayEvent};
+use crate::board::game_tree::{self, GameTree, Line};
+use crate::board::pgn::{self, PgnGame, PgnHeaders};
+use crate::board::pgn_reader::PgnReader;
+use crate::board::position::{Move, Position};
+use crate::board::{CapturedPieces, GameResult, PlayedMove, PlayedMoves, Replay};
+use crate::gui::{FenInput, MoveInput, PgnInput};
+use bevy_egui::egui::{Color32, Grid, Key, RichText, TextEdit, Ui};
+use itertools::Itertools;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Takes a slice of all played moves and groups them by move number
-fn group_played_moves(played_moves: &[PlayedMove]) -> Vec<Vec<PlayedMove>> {
-    played_moves.chunks(2).map(|chunk| chunk.to_vec()).collect()
+/// Groups all played moves by move number with their SAN,
+/// the first group only holds a single move if black moved first
+fn group_played_moves(played_moves: &PlayedMoves) -> Vec<Vec<(&PlayedMove, &str)>> {
+    let moves: Vec<_> = played_moves.iter().collect();
+    let split = match moves.first() {
+        Some((first, _)) if first.piece.color == PieceColor::Black => 1,
+        _ => 0,
+    };
+    let (first, rest) = moves.split_at(split);
+    let mut grouped: Vec<_> = rest.chunks(2).map(|chunk| chunk.to_vec()).collect();
+    if !first.is_empty() {
+        grouped.insert(0, first.to_vec());
+    }
+    grouped
 }
 
-pub fn build_played_moves_grid(ui: &mut Ui, played_moves: &[PlayedMove]) {
+/// Builds the list of played moves numbered from the move the game started with
+pub fn build_played_moves_grid(ui: &mut Ui, played_moves: &PlayedMoves) {
+    let first_move_nr = played_moves.start().fullmove_number;
     Grid::new("played_moves").show(ui, |ui| {
         let grouped_moves = group_played_moves(played_moves);
         for (move_nr, moves) in grouped_moves.iter().enumerate() {
             ui.label(
-                RichText::new(format!("{}.", move_nr + 1))
+                RichText::new(format!("{}.", first_move_nr + move_nr))
                     .strong()
                     .size(18.0),
             );
-            for played_move in moves {
-                ui.label(RichText::new(played_move.notation()).size(16.0));
+            if moves[0].0.piece.color == PieceColor::Black {
+                ui.label(RichText::new("...").size(16.0));
+            }
+            for (_, san) in moves {
+                ui.label(RichText::new(*san).size(16.0));
             }
             ui.end_row();
         }
     });
 }
+
+/// Builds the moves of a replayed game with its comments and annotations,
+/// variations are indented below the move they replace and the mainline move shown on the board is highlighted
+pub fn build_game_tree(ui: &mut Ui, tree: &GameTree, current: usize) {
+    build_line(ui, tree, &tree.mainline, 0, Some(current));
+}
+
+/// Builds a line whose first move is played at `first_ply`, `current` is only given for the mainline
+fn build_line(ui: &mut Ui, tree: &GameTree, line: &Line, first_ply: usize, current: Option<usize>) {
+    let mut start = 0;
+    loop {
+        // the moves up to the next one with variations are laid out in a wrapped row
+        let end = line.nodes[start..]
+            .iter()
+            .position(|node| !node.variations.is_empty())
+            .map_or(line.nodes.len(), |i| start + i + 1);
+        ui.horizontal_wrapped(|ui| {
+            if start == 0 {
+                for nag in &line.nags {
+                    let symbol =
+                        game_tree::nag_symbol(*nag).map_or(format!("${}", nag), String::from);
+                    ui.label(symbol);
+                }
+                for comment in &line.comments {
+                    ui.label(RichText::new(comment).italics().weak());
+                }
+            }
+            for index in start..end {
+                let node = &line.nodes[index];
+                let (number, color) = tree.move_number(first_ply + index);
+                let interrupted = index == start || !line.nodes[index - 1].comments.is_empty();
+                match color {
+                    PieceColor::White => {
+                        ui.label(RichText::new(format!("{}.", number)).strong());
+                    }
+                    PieceColor::Black if interrupted => {
+                        ui.label(RichText::new(format!("{}...", number)).strong());
+                    }
+                    PieceColor::Black => {}
+                }
+                let annotations: String = node
+                    .nags
+                    .iter()
+                    .map(|nag| {
+                        game_tree::nag_symbol(*nag).map_or(format!(" ${}", nag), String::from)
+                    })
+                    .collect();
+                let mut text = RichText::new(format!("{}{}", node.san, annotations)).size(16.0);
+                if current == Some(index + 1) {
+                    text = text.strong().background_color(Color32::DARK_GRAY);
+                }
+                ui.label(text);
+                for comment in &node.comments {
+                    ui.label(RichText::new(comment).italics().weak());
+                }
+            }
+        });
+        if end > start {
+            for (i, variation) in line.nodes[end - 1].variations.iter().enumerate() {
+                ui.indent((first_ply + end, i), |ui| {
+                    build_line(ui, tree, variation, first_ply + end - 1, None);
+                });
+            }
+        }
+        if end >= line.nodes.len() {
+            break;
+        }
+        start = end;
+    }
+}
+
+pub fn build_captured_pieces_list(ui: &mut Ui, captured_pieces: &CapturedPieces) {
+    for color in [PieceColor::White, PieceColor::Black] {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{}:", color)).strong().size(16.0));
+            let pieces = captured_pieces.taken_by(color).iter().join(" ");
+            ui.label(RichText::new(pieces).size(16.0));
+        });
+    }
+}
+
+pub fn build_game_result_label(ui: &mut Ui, result: &GameResult) {
+    ui.separator();
+    ui.label(RichText::new(result.to_string()).strong().size(18.0));
+}
+
+/// Builds the input to enter a move in SAN, returns the parsed move once the player confirms it
+pub fn build_move_input(
+    ui: &mut Ui,
+    position: &Position,
+    move_input: &mut MoveInput,
+) -> Option<Move> {
+    ui.separator();
+    let mut played = None;
+    ui.horizontal(|ui| {
+        let response = ui.add(TextEdit::singleline(&mut move_input.text).hint_text("Move"));
+        let entered = response.lost_focus() && ui.input().key_pressed(Key::Enter);
+        if ui.button("Play").clicked() || entered {
+            match position.parse_san(&move_input.text) {
+                Ok(mv) => {
+                    move_input.text.clear();
+                    move_input.error = None;
+                    played = Some(mv);
+                }
+                Err(err) => move_input.error = Some(format!("{}: {}", err, move_input.text)),
+            }
+        }
+    });
+    if let Some(error) = &move_input.error {
+        ui.label(RichText::new(error).color(Color32::RED));
+    }
+    played
+}
+
+/// Builds the buttons to copy the FEN of the current position and to load a position from FEN,
+/// returns the parsed position if the player wants to load it
+pub fn build_fen_controls(
+    ui: &mut Ui,
+    position: &Position,
+    fen_input: &mut FenInput,
+) -> Option<Position> {
+    ui.separator();
+    if ui.button("Copy FEN").clicked() {
+        ui.output().copied_text = position.to_fen();
+    }
+    ui.add(TextEdit::singleline(&mut fen_input.text).hint_text("FEN"));
+    let mut loaded = None;
+    if ui.button("Load FEN").clicked() {
+        match Position::from_fen(&fen_input.text) {
+            Ok(position) => {
+                fen_input.error = None;
+                loaded = Some(position);
+            }
+            Err(err) => fen_input.error = Some(format!("Invalid FEN: {}", err)),
+        }
+    }
+    if let Some(error) = &fen_input.error {
+        ui.label(RichText::new(error).color(Color32::RED));
+    }
+    loaded
+}
+
+/// Builds the button to save the current game as PGN file in the working directory
+pub fn build_pgn_export_button(
+    ui: &mut Ui,
+    played_moves: &PlayedMoves,
+    game_result: Option<&GameResult>,
+    replay: Option<&Replay>,
+) {
+    if !ui.button("Save PGN").clicked() {
+        return;
+    }
+    // a replayed game is saved as imported to keep its tags, variations and annotations
+    let pgn = match replay {
+        Some(replay) => pgn::write_pgn(&replay.game),
+        None => {
+            let headers = PgnHeaders {
+                event: "Casual game".to_string(),
+                site: "chess-engine".to_string(),
+                date: pgn::current_date(),
+                ..Default::default()
+            };
+            let tree = GameTree::from_moves(played_moves.start().clone(), &played_moves.moves());
+            let outcome = game_result.map(|result| result.outcome);
+            pgn::write_pgn(&PgnGame::new(&headers, tree, outcome))
+        }
+    };
+    let path = format!("game-{}.pgn", timestamp());
+    match std::fs::write(&path, pgn) {
+        Ok(()) => println!("INFO: saved game to {}", path),
+        Err(err) => eprintln!("ERROR: unable to save game to {}: {}", path, err),
+    }
+}
+
+/// Builds the button to save the current position as SVG diagram with the last move highlighted
+pub fn build_diagram_export_button(ui: &mut Ui, position: &Position, played_moves: &PlayedMoves) {
+    if !ui.button("Save SVG").clicked() {
+        return;
+    }
+    let options = DiagramOptions {
+        coordinates: true,
+        last_move: played_moves
+            .iter()
+            .last()
+            .map(|(played_move, _)| (played_move.source, played_move.target)),
+        ..Default::default()
+    };
+    let path = format!("position-{}.svg", timestamp());
+    let result = diagram::render_svg(position, &options, Path::new("assets"))
+        .and_then(|svg| std::fs::write(&path, svg));
+    match result {
+        Ok(()) => println!("INFO: saved diagram to {}", path),
+        Err(err) => eprintln!("ERROR: unable to save diagram to {}: {}", path, err),
+    }
+}
+
+/// Returns the seconds since the Unix epoch to name saved files
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Builds the input for the path of a PGN file and the button to import it,
+/// returns the game read from the file if the player wants to replay it
+pub fn build_pgn_import_controls(ui: &mut Ui, pgn_input: &mut PgnInput) -> Option<PgnGame> {
+    ui.separator();
+    ui.add(TextEdit::singleline(&mut pgn_input.path).hint_text("PGN file"));
+    if !ui.button("Load PGN").clicked() {
+        if let Some(error) = &pgn_input.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+        return None;
+    }
+    // only the first game of a database is read
+    let path = pgn_input.path.trim();
+    let game = File::open(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(
+            |file| match PgnReader::new(BufReader::new(file)).games().next() {
+                Some(game) => game.map_err(|err| format!("Invalid PGN: {}", err)),
+                None => Err(format!("No game in {}", path)),
+            },
+        );
+    match game {
+        Ok(game) => {
+            pgn_input.error = None;
+            Some(game)
+        }
+        Err(error) => {
+            ui.label(RichText::new(&error).color(Color32::RED));
+            pgn_input.error = Some(error);
+            None
+        }
+    }
+}
+
+/// Builds the button to let the engine play the next move, which stops the engine while it is searching
+pub fn build_engine_controls(ui: &mut Ui, searching: bool) -> Option<EngineEvent> {
+    ui.separator();
+    match searching {
+        true => ui
+            .button("Stop engine")
+            .clicked()
+            .then_some(EngineEvent::Stop),
+        false => ui
+            .button("Engine move")
+            .clicked()
+            .then_some(EngineEvent::Start),
+    }
+}
+
+/// Builds the buttons to step through the replayed game, returns the requested step
+pub fn build_replay_controls(ui: &mut Ui, replay: &mut Replay) -> Option<ReplayEvent> {
+    ui.separator();
+    ui.label(format!("Move {}/{}", replay.current, replay.moves.len()));
+    let mut event = None;
+    ui.horizontal(|ui| {
+        if ui.button("|<").clicked() {
+            event = Some(ReplayEvent::Start);
+        }
+        if ui.button("<").clicked() {
+            event = Some(ReplayEvent::Back);
+        }
+        if ui.button(">").clicked() {
+            event = Some(ReplayEvent::Forward);
+        }
+        if ui.button(">|").clicked() {
+            event = Some(ReplayEvent::End);
+        }
+    });
+    if ui.checkbox(&mut replay.autoplay, "Autoplay").changed() && replay.autoplay {
+        replay.timer.reset();
+    }
+    if ui.button("Exit replay").clicked() {
+        event = Some(ReplayEvent::Exit);
+    }
+    event
+}
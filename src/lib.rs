@@ -0,0 +1,22 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use crate::board::components::{Location, Piece};
+use crate::constants::SQUARE_SIZE;
+use bevy::prelude::*;
+
+pub mod board;
+pub mod cli;
+pub mod constants;
+pub mod gui;
+pub mod macros;
+pub mod resources;
+pub mod terminal;
+
+/// Holds the original`Transform` for `BoardCamera` entity
+/// to offset the camera after drawing UI elements
+pub struct OriginalCameraTransforms {
+    pub board_camera: Transform,
+}
+
+#[derive(Component)]
+pub struct BoardCamera;
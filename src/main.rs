@@ -1,30 +1,17 @@
-use crate::board::components::{Location, Piece};
-use crate::board::plugin::BoardPlugin;
-use crate::constants::{SQUARE_SIZE, WINDOW_BACKGROUND_COLOR, WINDOW_HEIGHT, WINDOW_WIDTH};
-use crate::gui::plugin::GuiPlugin;
-use crate::gui::OccupiedScreenSpace;
-use crate::resources::ResourcePlugin;
 use bevy::prelude::*;
 use bevy::winit::WinitSettings;
 use bevy_egui::EguiPlugin;
 use bevy_inspector_egui::{RegisterInspectable, WorldInspectorPlugin};
 use bevy_prototype_lyon::prelude::*;
 use bevy_svg::prelude::*;
-
-mod board;
-mod constants;
-mod gui;
-pub mod macros;
-mod resources;
-
-/// Holds the original`Transform` for `BoardCamera` entity
-/// to offset the camera after drawing UI elements
-pub struct OriginalCameraTransforms {
-    pub board_camera: Transform,
-}
-
-#[derive(Component)]
-pub struct BoardCamera;
+use chess_engine::board::components::{Location, Piece};
+use chess_engine::board::plugin::BoardPlugin;
+use chess_engine::cli;
+use chess_engine::constants::{WINDOW_BACKGROUND_COLOR, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chess_engine::gui::plugin::GuiPlugin;
+use chess_engine::gui::OccupiedScreenSpace;
+use chess_engine::resources::ResourcePlugin;
+use chess_engine::{BoardCamera, OriginalCameraTransforms};
 
 pub struct DebugPlugin;
 
@@ -39,6 +26,11 @@ impl Plugin for DebugPlugin {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::run(&args) {
+        return;
+    }
+
     App::new()
         .insert_resource(Msaa { samples: 4 })
         .insert_resource(WindowDescriptor {
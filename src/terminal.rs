@@ -0,0 +1,237 @@
+use crate::board::components::{File, Location, Piece, PieceColor, PieceType};
+use crate::board::position::Position;
+use crate::board::rules::{self, DrawRules, GameStatus, PositionHistory};
+use crate::board::{GameOverReason, GameResult};
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "Enter moves like Nf3 or g1f3, other commands are:
+  moves  list the legal moves
+  fen    print the FEN of the position
+  board  print the board again
+  draw   claim a draw if the rules allow it
+  quit   leave the game";
+
+/// How the pieces are drawn on the board
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Glyphs {
+    /// Chess symbols like ♔, which need a font that has them
+    Unicode,
+    /// The piece letters, uppercase for white and lowercase for black
+    Ascii,
+}
+
+impl Glyphs {
+    pub fn piece(&self, piece: &Piece) -> char {
+        match self {
+            Glyphs::Ascii => match piece.color {
+                PieceColor::White => piece.notation(),
+                PieceColor::Black => piece.notation().to_ascii_lowercase(),
+            },
+            Glyphs::Unicode => match (piece.color, piece.kind) {
+                (PieceColor::White, PieceType::King) => '♔',
+                (PieceColor::White, PieceType::Queen) => '♕',
+                (PieceColor::White, PieceType::Rook) => '♖',
+                (PieceColor::White, PieceType::Bishop) => '♗',
+                (PieceColor::White, PieceType::Knight) => '♘',
+                (PieceColor::White, PieceType::Pawn) => '♙',
+                (PieceColor::Black, PieceType::King) => '♚',
+                (PieceColor::Black, PieceType::Queen) => '♛',
+                (PieceColor::Black, PieceType::Rook) => '♜',
+                (PieceColor::Black, PieceType::Bishop) => '♝',
+                (PieceColor::Black, PieceType::Knight) => '♞',
+                (PieceColor::Black, PieceType::Pawn) => '♟',
+            },
+        }
+    }
+}
+
+/// Draws the board with white at the bottom, ranks on the left and files below
+pub fn render_board(position: &Position, glyphs: Glyphs) -> String {
+    let mut board = String::new();
+    for y in (0..8).rev() {
+        board.push_str(&(y + 1).to_string());
+        for x in 0..8 {
+            let square = match position.piece_at(&Location::new(x, y)) {
+                Some(piece) => glyphs.piece(&piece),
+                None => '.',
+            };
+            board.push(' ');
+            board.push(square);
+        }
+        board.push('\n');
+    }
+    board.push(' ');
+    for x in 0..8 {
+        board.push_str(&format!(" {}", File::from_index(x)));
+    }
+    board.push('\n');
+    board
+}
+
+/// A game played by reading moves and commands line by line,
+/// it follows the same rules as the board of the app
+pub struct TerminalGame {
+    pub position: Position,
+    history: PositionHistory,
+    draw_rules: DrawRules,
+    claimable: Option<GameOverReason>,
+    result: Option<GameResult>,
+    glyphs: Glyphs,
+}
+
+impl TerminalGame {
+    pub fn new(position: Position, glyphs: Glyphs) -> Self {
+        let history = PositionHistory(vec![position.zobrist_key()]);
+        let mut game = Self {
+            position,
+            history,
+            draw_rules: DrawRules::default(),
+            claimable: None,
+            result: None,
+            glyphs,
+        };
+        // the game may already be over in the start position
+        game.update_status();
+        game
+    }
+
+    /// Plays the game until it is over, the input ends or the player quits and returns the result
+    pub fn run(
+        &mut self,
+        input: impl BufRead,
+        output: &mut impl Write,
+    ) -> io::Result<Option<GameResult>> {
+        writeln!(output, "{}", render_board(&self.position, self.glyphs))?;
+        let mut lines = input.lines();
+        while self.result.is_none() {
+            let color = self.position.side_to_move;
+            write!(
+                output,
+                "{} {} to move> ",
+                self.position.fullmove_number, color
+            )?;
+            output.flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            let command = line.trim();
+            match command {
+                "" => {}
+                "quit" | "exit" => break,
+                "help" => writeln!(output, "{}", HELP)?,
+                "board" => writeln!(output, "{}", render_board(&self.position, self.glyphs))?,
+                "fen" => writeln!(output, "{}", self.position.to_fen())?,
+                "moves" => {
+                    let mut moves: Vec<String> = self
+                        .position
+                        .legal_moves()
+                        .iter()
+                        .map(|mv| self.position.san(mv))
+                        .collect();
+                    moves.sort();
+                    writeln!(output, "{}", moves.join(" "))?;
+                }
+                "draw" => match self.claimable {
+                    Some(reason) => self.result = Some(GameResult::draw(reason)),
+                    None => writeln!(output, "no draw can be claimed")?,
+                },
+                _ => match self.play(command) {
+                    Ok(san) => {
+                        writeln!(output, "{}\n", san)?;
+                        writeln!(output, "{}", render_board(&self.position, self.glyphs))?;
+                        if let Some(reason) = self.claimable {
+                            writeln!(output, "a draw can be claimed ({})", reason)?;
+                        }
+                    }
+                    Err(err) => writeln!(output, "{}", err)?,
+                },
+            }
+        }
+        if let Some(result) = self.result {
+            writeln!(output, "game over {}", result)?;
+        }
+        Ok(self.result)
+    }
+
+    /// Plays a move given in SAN or UCI notation and returns its SAN
+    pub fn play(&mut self, text: &str) -> Result<String, String> {
+        let mv = match self.position.parse_san(text) {
+            Ok(mv) => mv,
+            Err(san_err) => self
+                .position
+                .parse_uci(text)
+                .map_err(|_| format!("{} '{}'", san_err, text))?,
+        };
+        let san = self.position.san(&mv);
+        self.position.make_move(mv);
+        self.history.0.push(self.position.zobrist_key());
+        self.update_status();
+        Ok(san)
+    }
+
+    fn update_status(&mut self) {
+        // a claimable draw is only valid for the move right after it arose
+        self.claimable = None;
+        match rules::game_status(&self.position, &self.history, &self.draw_rules) {
+            GameStatus::Over(result) => self.result = Some(result),
+            GameStatus::Claimable(reason) => self.claimable = Some(reason),
+            GameStatus::Ongoing => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::fen::INITIAL_FEN;
+    use crate::board::GameOutcome;
+
+    fn play(fen: &str, input: &str) -> (Option<GameResult>, String) {
+        let mut game = TerminalGame::new(Position::from_fen(fen).unwrap(), Glyphs::Ascii);
+        let mut output = Vec::new();
+        let result = game.run(input.as_bytes(), &mut output).unwrap();
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn renders_board() {
+        let position = Position::from_fen(INITIAL_FEN).unwrap();
+        let board = render_board(&position, Glyphs::Ascii);
+        assert!(board.starts_with("8 r n b q k b n r\n7 p p p p p p p p\n6 . . . . . . . .\n"));
+        assert!(board.ends_with("1 R N B Q K B N R\n  a b c d e f g h\n"));
+        let board = render_board(&position, Glyphs::Unicode);
+        assert!(board.starts_with("8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜\n"));
+    }
+
+    #[test]
+    fn plays_until_checkmate() {
+        let (result, output) = play(INITIAL_FEN, "f3\ne7e5\nKe2\ng4\nQh4\ne4\n");
+        assert_eq!(
+            result.map(|result| result.outcome),
+            Some(GameOutcome::BlackWins)
+        );
+        assert!(output.contains("Qh4#"));
+        assert!(output.contains("illegal move 'Ke2'"));
+        assert!(output.ends_with("game over 0-1 (Checkmate)\n"));
+    }
+
+    #[test]
+    fn applies_draw_rules() {
+        let (result, _) = play("8/8/4k3/8/8/3NK3/8/8 w - - 0 1", "");
+        assert_eq!(
+            result,
+            Some(GameResult::draw(GameOverReason::InsufficientMaterial))
+        );
+
+        let (result, output) = play(
+            INITIAL_FEN,
+            "draw\nNf3\nNf6\nNg1\nNg8\nNf3\nNf6\nNg1\nNg8\nquit\n",
+        );
+        assert!(output.contains("no draw can be claimed"));
+        assert_eq!(
+            result,
+            Some(GameResult::draw(GameOverReason::ThreefoldRepetition))
+        );
+    }
+}